@@ -1,10 +1,13 @@
 use cosmwasm_schema::write_api;
-use quadratic_grant::contract::sv::{ContractExecMsg, ContractQueryMsg, InstantiateMsg};
+use quadratic_grant::contract::sv::{
+    ContractExecMsg, ContractQueryMsg, InstantiateMsg, MigrateMsg,
+};
 
 fn main() {
     write_api! {
         instantiate: InstantiateMsg,
         execute: ContractExecMsg,
         query: ContractQueryMsg,
+        migrate: MigrateMsg,
     }
 }
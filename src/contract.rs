@@ -3,43 +3,142 @@
 //! Version: 0.1.0
 //! License: Apache-2.0
 
+use std::collections::BTreeMap;
+
 use cosmwasm_std::{
-    coins, Addr, BankMsg, DenomUnit, Deps, DepsMut, Empty, Env, Event, MessageInfo, Order,
-    Response, StdError, StdResult, Uint128,
+    coins, to_json_vec, Addr, BankMsg, Decimal, DenomUnit, Deps, DepsMut, Empty, Env, Event,
+    MessageInfo, Order, Response, StdError, StdResult, Storage, Uint128,
 };
-use cw_storage_plus::{Item, Map};
+use cw_storage_plus::{Bound, Item, Map};
 use schemars;
 use sylvia::contract;
 
 use crate::{
     error::ContractError,
-    helper::{math, signature},
-    responses::AdminListResp,
-    state::{Project, ProjectStatus, Round, RoundStatus},
+    helper::{
+        fixed::{self, LEGACY_PRECISION, MAX_PRECISION},
+        hash, merkle, signature,
+    },
+    responses::{
+        AdminListResp, DistributionResp, FeeCollectorResp, GrantResp, LeaderboardEntry,
+        LeaderboardResp, MerkleClaimResp, MilestonesResp, PairwiseAreasResp, PauseStateResp,
+        ProjectArea, ProposalsResp, QueuedActionsResp, RolesResp, RoundStatsResp, SnapshotResp,
+        TreasuryResp, VestingResp, VoterEntry, VoterWeightResp, VotersResp,
+    },
+    state::{
+        AdminAction, MatchingStrategy, Milestone, PayoutMode, Project, ProjectGrant, ProjectSort,
+        ProjectStatus, Proposal, ProposalAction, QueuedAction, Role, Round, RoundStatus,
+        WeightCurve, WeightPolicy, WeightSource,
+    },
 };
 
+const CONTRACT_NAME: &str = concat!("crates.io:", env!("CARGO_PKG_NAME"));
+const CONTRACT_VERSION: &str = env!("CARGO_PKG_VERSION");
 const INJ_DECIMALS: u32 = 18;
+const DEFAULT_TOP_N: u32 = 10;
+const DEFAULT_LIMIT: u32 = 10;
+const MAX_CAP_BPS: u64 = 10_000;
+const MAX_SCORE_TYPE_LEN: usize = 64;
+const MAX_LIMIT: u32 = 100;
+const MAX_FEE_BPS: u64 = 10_000;
+const MAX_PAIRWISE_PROJECTS: u64 = 20;
+const MAX_PAIRWISE_VOTERS: u64 = 50;
+const ALL_ROLES: [Role; 4] = [
+    Role::RoundManager,
+    Role::ProjectCurator,
+    Role::SignerManager,
+    Role::Treasurer,
+];
 
 pub struct QGContract<'a> {
     pub(crate) owner: Item<'a, Addr>,
+    pub(crate) paused: Item<'a, bool>,
+    pub(crate) treasury: Item<'a, Addr>,
+    pub(crate) fee_collector: Item<'a, Addr>,
+    pub(crate) timelock_delay: Item<'a, u64>,
+    pub(crate) precision: Item<'a, u32>,
+    pub(crate) deposits_tracked: Item<'a, bool>,
+    pub(crate) queued_actions: Map<'a, u64, QueuedAction>,
+    pub(crate) next_action_id: Item<'a, u64>,
+    pub(crate) approval_threshold: Item<'a, u64>,
+    pub(crate) proposals: Map<'a, u64, Proposal>,
+    pub(crate) next_proposal_id: Item<'a, u64>,
     pub(crate) admins: Map<'a, &'a Addr, Empty>,
+    pub(crate) roles: Map<'a, &'a Addr, Vec<Role>>,
     pub(crate) rounds: Map<'a, &'a str, Round>,
     pub(crate) current_round: Item<'a, u64>,
     pub(crate) projects: Map<'a, (&'a str, &'a str), Project>, // (round_id, project_id)
     pub(crate) votes: Map<'a, (&'a str, &'a str, &'a Addr), u128>, // (round_id, project_id, voter)
+    pub(crate) voted_projects: Map<'a, (&'a str, &'a Addr, &'a str), Empty>, // (round_id, voter, project_id)
+    pub(crate) voter_areas: Map<'a, (&'a str, &'a str, &'a Addr), u128>, // (round_id, project_id, voter) -> area added
+    pub(crate) clusters: Map<'a, (&'a str, &'a Addr), u64>, // (round_id, voter) -> cluster id
+    pub(crate) cluster_votes: Map<'a, (&'a str, &'a str, &'a str), u128>, // (round_id, project_id, cluster key) -> votes
+    pub(crate) cluster_area: Map<'a, (&'a str, &'a str), u128>, // (round_id, project_id) -> cluster area
+    pub(crate) stakes: Map<'a, (&'a str, &'a Addr), Uint128>, // (round_id, voter) -> snapshotted stake
+    pub(crate) pair_overlaps: Map<'a, (&'a str, &'a str), u128>, // (round_id, voter pair) -> overlap
+    pub(crate) pair_products: Map<'a, (&'a str, &'a str, &'a str), u128>, // (round_id, voter pair, project_id)
+    pub(crate) pairwise_area: Map<'a, (&'a str, &'a str), u128>, // (round_id, project_id) -> pairwise area
+    pub(crate) voters: Map<'a, (&'a str, &'a Addr), u128>, // (round_id, voter) -> total contribution
+    pub(crate) voter_weights: Map<'a, (&'a str, &'a Addr), u128>, // (round_id, voter) -> weight of the latest attested ballot
+    pub(crate) contributions: Map<'a, (&'a str, &'a str, &'a Addr), u128>, // (round_id, project_id, voter)
+    pub(crate) net_contributions: Map<'a, (&'a str, &'a str, &'a Addr), u128>, // (round_id, project_id, voter) -> after fees
+    pub(crate) grants: Map<'a, (&'a str, &'a str), ProjectGrant>, // (round_id, project_id)
+    pub(crate) claims: Map<'a, (&'a str, &'a str), u128>, // (round_id, project_id) -> claimed matching
+    pub(crate) merkle_claims: Map<'a, (&'a str, u64), u64>, // (round_id, index / 64) -> claimed bitmap
+    pub(crate) vesting_halts: Map<'a, (&'a str, &'a str), u64>, // (round_id, project_id) -> halted at
+    pub(crate) milestones: Map<'a, (&'a str, &'a str), Vec<Milestone>>, // (round_id, project_id) -> milestones
+    pub(crate) milestone_returns: Map<'a, (&'a str, &'a str), u128>, // (round_id, project_id) -> returned
+}
+
+impl Default for QGContract<'_> {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 #[contract]
 #[error(ContractError)]
+#[warn(clippy::too_many_arguments)]
 impl QGContract<'_> {
     pub const fn new() -> Self {
         Self {
             owner: Item::new("owner"),
+            paused: Item::new("paused"),
+            treasury: Item::new("treasury"),
+            fee_collector: Item::new("fee_collector"),
+            timelock_delay: Item::new("timelock_delay"),
+            precision: Item::new("precision"),
+            deposits_tracked: Item::new("deposits_tracked"),
+            queued_actions: Map::new("queued_actions"),
+            next_action_id: Item::new("next_action_id"),
+            approval_threshold: Item::new("approval_threshold"),
+            proposals: Map::new("proposals"),
+            next_proposal_id: Item::new("next_proposal_id"),
             admins: Map::new("admins"),
+            roles: Map::new("roles"),
             rounds: Map::new("rounds"),
             current_round: Item::new("current_round"),
             projects: Map::new("projects"),
             votes: Map::new("votes"),
+            voted_projects: Map::new("voted_projects"),
+            voter_areas: Map::new("voter_areas"),
+            clusters: Map::new("clusters"),
+            cluster_votes: Map::new("cluster_votes"),
+            cluster_area: Map::new("cluster_area"),
+            stakes: Map::new("stakes"),
+            pair_overlaps: Map::new("pair_overlaps"),
+            pair_products: Map::new("pair_products"),
+            pairwise_area: Map::new("pairwise_area"),
+            voters: Map::new("voters"),
+            voter_weights: Map::new("voter_weights"),
+            contributions: Map::new("contributions"),
+            net_contributions: Map::new("net_contributions"),
+            grants: Map::new("grants"),
+            claims: Map::new("claims"),
+            merkle_claims: Map::new("merkle_claims"),
+            vesting_halts: Map::new("vesting_halts"),
+            milestones: Map::new("milestones"),
+            milestone_returns: Map::new("milestone_returns"),
         }
     }
 
@@ -56,19 +155,742 @@ impl QGContract<'_> {
         Ok(())
     }
 
-    fn check_admin_permission(
+    /// Every address, admins included, needs the specific role granted by the owner.
+    fn check_role(
         &self,
         deps: &DepsMut,
         info: &MessageInfo,
+        role: Role,
     ) -> Result<(), ContractError> {
-        if !self.admins.has(deps.storage, &info.sender) {
-            return Err(ContractError::Unauthorized {
+        let roles = self
+            .roles
+            .may_load(deps.storage, &info.sender)?
+            .unwrap_or_default();
+        if !roles.contains(&role) {
+            return Err(ContractError::MissingRole {
                 sender: info.sender.clone(),
+                role,
+            });
+        }
+        Ok(())
+    }
+
+    fn check_action_permission(
+        &self,
+        deps: &DepsMut,
+        info: &MessageInfo,
+        action: &AdminAction,
+    ) -> Result<(), ContractError> {
+        match action {
+            AdminAction::SetPubkey { .. } => self.check_role(deps, info, Role::SignerManager),
+            AdminAction::DelAdmin { .. } => self.check_owner_permission(deps, info),
+            AdminAction::Withdraw { .. } => self.check_role(deps, info, Role::Treasurer),
+        }
+    }
+
+    /// Queues `action` when a timelock delay is configured. Returns `None` if it should run now.
+    fn queue_action(
+        &self,
+        deps: &mut DepsMut,
+        env: &Env,
+        info: &MessageInfo,
+        action: AdminAction,
+    ) -> Result<Option<Response>, ContractError> {
+        let delay = self
+            .timelock_delay
+            .may_load(deps.storage)?
+            .unwrap_or_default();
+        if delay == 0 {
+            return Ok(None);
+        }
+
+        let id = self
+            .next_action_id
+            .may_load(deps.storage)?
+            .unwrap_or_default()
+            + 1;
+        self.next_action_id.save(deps.storage, &id)?;
+
+        let queued = QueuedAction {
+            id,
+            action,
+            proposer: info.sender.to_string(),
+            eta: env.block.time.seconds() + delay,
+        };
+        self.queued_actions.save(deps.storage, id, &queued)?;
+
+        let resp = Response::new()
+            .add_attribute("action", "queue_action")
+            .add_event(
+                Event::new("action_queued")
+                    .add_attribute("id", id.to_string())
+                    .add_attribute("proposer", info.sender.as_str())
+                    .add_attribute("eta", queued.eta.to_string()),
+            );
+        Ok(Some(resp))
+    }
+
+    fn run_action(
+        &self,
+        deps: DepsMut,
+        env: Env,
+        action: AdminAction,
+    ) -> Result<Response, ContractError> {
+        match action {
+            AdminAction::SetPubkey { round_id, pubkey } => {
+                self.apply_set_pubkey(deps, round_id, pubkey)
+            }
+            AdminAction::DelAdmin { admin } => self.apply_del_admin(deps, admin),
+            AdminAction::Withdraw { round_id, amount } => {
+                self.apply_withdraw(deps, env, round_id, amount)
+            }
+        }
+    }
+
+    /// Direct calls are rejected once more than one admin approval is required.
+    fn check_no_approval_required(&self, deps: &DepsMut) -> Result<(), ContractError> {
+        let threshold = self
+            .approval_threshold
+            .may_load(deps.storage)?
+            .unwrap_or_default();
+        if threshold > 1 {
+            return Err(ContractError::ApprovalRequired { threshold });
+        }
+        Ok(())
+    }
+
+    /// Voting and payouts are blocked while either the contract or the round is paused.
+    fn check_not_paused(&self, deps: &DepsMut, round: &Round) -> Result<(), ContractError> {
+        if self.paused.may_load(deps.storage)?.unwrap_or_default() {
+            return Err(ContractError::Paused);
+        }
+        if round.paused {
+            return Err(ContractError::RoundPaused { round_id: round.id });
+        }
+        Ok(())
+    }
+
+    /// Loads every project of a round, ordered by `sort_by` (descending) and then by id.
+    fn sorted_projects(
+        &self,
+        deps: Deps,
+        round_id: u64,
+        sort_by: ProjectSort,
+    ) -> StdResult<Vec<Project>> {
+        let mut projects = self
+            .projects
+            .prefix(&round_id.to_string())
+            .range(deps.storage, None, None, Order::Ascending)
+            .map(|item| item.map(|(_, project)| project))
+            .collect::<StdResult<Vec<Project>>>()?;
+
+        projects.sort_by(|a, b| {
+            let ordering = match sort_by {
+                ProjectSort::Area => b.area.cmp(&a.area),
+                ProjectSort::Contribution => b.contribution.cmp(&a.contribution),
+                ProjectSort::Votes => b.votes.cmp(&a.votes),
+            };
+            ordering.then(a.id.cmp(&b.id))
+        });
+        Ok(projects)
+    }
+
+    /// A project takes part in matching only if it is not banned and reaches the round thresholds.
+    fn is_eligible(round: &Round, project: &Project) -> bool {
+        project.status == ProjectStatus::OK
+            && project.voter_count >= round.min_voters
+            && project.contribution >= round.min_contribution.u128()
+    }
+
+    /// Weight of a signed ballot with attested `score`, scaled by `10^round.precision`.
+    pub(crate) fn curve_weight(round: &Round, score: u64) -> StdResult<u128> {
+        let precision = round.precision;
+        let one = 10u128.pow(precision);
+        Ok(match &round.weight_curve {
+            // plus 2 to avoid 0, saturating for staking scores capped at u64::MAX
+            WeightCurve::Log2 => fixed::log2(score.saturating_add(2), precision)?,
+            WeightCurve::Sqrt => fixed::sqrt(score as u128 + 1, precision),
+            WeightCurve::Linear { slope, cap } => {
+                // slope * score first, a small slope alone may not survive the precision
+                let weight = one
+                    + slope
+                        .atomics()
+                        .multiply_ratio(score as u128 * one, 10u128.pow(Decimal::DECIMAL_PLACES))
+                        .u128();
+                weight.min(fixed::from_decimal(*cap, precision))
+            }
+            WeightCurve::Piecewise { steps } => steps
+                .iter()
+                .rev()
+                .find(|step| step.min_score <= score)
+                .map(|step| fixed::from_decimal(step.weight, precision))
+                .unwrap_or(one),
+            WeightCurve::Constant { weight } => fixed::from_decimal(*weight, precision),
+        })
+    }
+
+    fn staked_amount(
+        &self,
+        deps: Deps,
+        round: &Round,
+        voter: &Addr,
+        snapshot: bool,
+    ) -> StdResult<Uint128> {
+        if snapshot {
+            return Ok(self
+                .stakes
+                .may_load(deps.storage, (&round.id.to_string(), voter))?
+                .unwrap_or_default());
+        }
+        let denom = deps.querier.query_bonded_denom()?;
+        Ok(deps
+            .querier
+            .query_all_delegations(voter)?
+            .iter()
+            .filter(|delegation| delegation.amount.denom == denom)
+            .map(|delegation| delegation.amount.amount)
+            .sum())
+    }
+
+    /// Moves a round in voting to `precision`. Voter weights and areas accumulated from ballots
+    /// are multiplied by the change of scale, except linear areas which are counted in votes.
+    /// Pairwise and cluster areas are derived from roots of the current votes, so they are
+    /// rebuilt at the new precision to stay consistent with later updates.
+    fn rescale_areas(
+        &self,
+        storage: &mut dyn Storage,
+        round: &mut Round,
+        precision: u32,
+    ) -> StdResult<()> {
+        let round_id = round.id.to_string();
+        let factor = 10u128.pow(precision - round.precision);
+        round.precision = precision;
+
+        if round.matching_strategy != MatchingStrategy::Linear {
+            round.total_area *= factor;
+            let projects = self
+                .projects
+                .prefix(&round_id)
+                .range(storage, None, None, Order::Ascending)
+                .collect::<StdResult<Vec<_>>>()?;
+            for (project_id, mut project) in projects {
+                project.area *= factor;
+                self.projects
+                    .save(storage, (&round_id, &project_id), &project)?;
+            }
+            let voter_areas = self
+                .voter_areas
+                .sub_prefix(&round_id)
+                .range(storage, None, None, Order::Ascending)
+                .collect::<StdResult<Vec<_>>>()?;
+            for ((project_id, voter), area) in voter_areas {
+                self.voter_areas.save(
+                    storage,
+                    (&round_id, &project_id, &voter),
+                    &(area * factor),
+                )?;
+            }
+        }
+        let voter_weights = self
+            .voter_weights
+            .prefix(&round_id)
+            .range(storage, None, None, Order::Ascending)
+            .collect::<StdResult<Vec<_>>>()?;
+        for (voter, weight) in voter_weights {
+            self.voter_weights
+                .save(storage, (&round_id, &voter), &(weight * factor))?;
+        }
+
+        match round.matching_strategy {
+            MatchingStrategy::Pairwise { threshold } => {
+                self.rebuild_pairwise_areas(storage, round, threshold)
+            }
+            MatchingStrategy::Cluster => self.rebuild_cluster_areas(storage, round),
+            _ => Ok(()),
+        }
+    }
+
+    /// Recomputes the pairwise state of `round` from its votes, see `update_pairwise_areas`.
+    fn rebuild_pairwise_areas(
+        &self,
+        storage: &mut dyn Storage,
+        round: &Round,
+        threshold: u128,
+    ) -> StdResult<()> {
+        let round_id = round.id.to_string();
+        for map in [&self.pairwise_area, &self.pair_overlaps] {
+            let keys = map
+                .prefix(&round_id)
+                .keys(storage, None, None, Order::Ascending)
+                .collect::<StdResult<Vec<_>>>()?;
+            for key in keys {
+                map.remove(storage, (&round_id, &key));
+            }
+        }
+        let keys = self
+            .pair_products
+            .sub_prefix(&round_id)
+            .keys(storage, None, None, Order::Ascending)
+            .collect::<StdResult<Vec<_>>>()?;
+        for (pair, project_id) in keys {
+            self.pair_products
+                .remove(storage, (&round_id, &pair, &project_id));
+        }
+
+        let mut roots: BTreeMap<String, Vec<(Addr, u128)>> = BTreeMap::new();
+        for item in self
+            .votes
+            .sub_prefix(&round_id)
+            .range(storage, None, None, Order::Ascending)
+        {
+            let ((project_id, voter), votes) = item?;
+            let root = fixed::sqrt(votes, round.precision);
+            roots.entry(project_id).or_default().push((voter, root));
+        }
+
+        let mut products: Vec<(String, String, u128)> = vec![];
+        let mut overlaps: BTreeMap<String, u128> = BTreeMap::new();
+        for (project_id, voters) in roots.iter() {
+            for (i, (voter, root)) in voters.iter().enumerate() {
+                for (other, other_root) in voters[i + 1..].iter() {
+                    let product = root * other_root;
+                    if product == 0 {
+                        continue;
+                    }
+                    let pair = Self::pair_key(voter, other);
+                    *overlaps.entry(pair.clone()).or_default() += product;
+                    products.push((pair, project_id.clone(), product));
+                }
+            }
+        }
+
+        let threshold = threshold * 10u128.pow(round.precision * 2);
+        let mut areas: BTreeMap<String, u128> = BTreeMap::new();
+        for (pair, project_id, product) in products {
+            *areas.entry(project_id.clone()).or_default() +=
+                Self::pairwise_term(product, overlaps[&pair], threshold);
+            self.pair_products
+                .save(storage, (&round_id, &pair, &project_id), &product)?;
+        }
+        for (pair, overlap) in overlaps {
+            self.pair_overlaps
+                .save(storage, (&round_id, &pair), &overlap)?;
+        }
+        for (project_id, area) in areas {
+            self.pairwise_area
+                .save(storage, (&round_id, &project_id), &area)?;
+        }
+        Ok(())
+    }
+
+    /// Recomputes the cluster-match areas of `round` from the votes summed per cluster.
+    fn rebuild_cluster_areas(&self, storage: &mut dyn Storage, round: &Round) -> StdResult<()> {
+        let round_id = round.id.to_string();
+        let mut areas: BTreeMap<String, u128> = BTreeMap::new();
+        for item in
+            self.cluster_votes
+                .sub_prefix(&round_id)
+                .range(storage, None, None, Order::Ascending)
+        {
+            let ((project_id, _), votes) = item?;
+            *areas.entry(project_id).or_default() += fixed::sqrt(votes, round.precision);
+        }
+        for (project_id, area) in areas {
+            self.cluster_area
+                .save(storage, (&round_id, &project_id), &area)?;
+        }
+        Ok(())
+    }
+
+    /// Share of a pair's `product` on a project once the pair co-funds `k`, `threshold` is scaled
+    /// like the products.
+    fn pairwise_term(product: u128, k: u128, threshold: u128) -> u128 {
+        Uint128::from(product)
+            .multiply_ratio(threshold, threshold + k)
+            .u128()
+    }
+
+    fn pair_key(a: &Addr, b: &Addr) -> String {
+        if a < b {
+            format!("{}:{}", a, b)
+        } else {
+            format!("{}:{}", b, a)
+        }
+    }
+
+    /// Keeps pairwise-bounded areas up to date when `voter` changes their votes on a project.
+    /// Every pair of voters adds `sqrt(c_i * c_j)` to each project they both fund, discounted
+    /// by `threshold / (threshold + k)` where `k` is the pair's overlap across the whole round,
+    /// so a change re-weights the pair's terms on all of their shared projects. Pairwise rounds
+    /// cap projects and voters per project, which bounds this to `MAX_PAIRWISE_VOTERS` pairs
+    /// on at most `MAX_PAIRWISE_PROJECTS` projects each.
+    #[allow(clippy::too_many_arguments)]
+    fn update_pairwise_areas(
+        &self,
+        deps: &mut DepsMut,
+        round_id: &str,
+        project_id: &str,
+        voter: &Addr,
+        old_votes: u128,
+        new_votes: u128,
+        threshold: u128,
+        precision: u32,
+    ) -> StdResult<()> {
+        // roots are scaled like the classic area, so products are scaled twice
+        let old_root = fixed::sqrt(old_votes, precision);
+        let new_root = fixed::sqrt(new_votes, precision);
+        if old_root == new_root {
+            return Ok(());
+        }
+        let threshold = threshold * 10u128.pow(precision * 2);
+        let term = |product: u128, k: u128| Self::pairwise_term(product, k, threshold);
+
+        let others = self
+            .votes
+            .prefix((round_id, project_id))
+            .range(deps.storage, None, None, Order::Ascending)
+            .collect::<StdResult<Vec<_>>>()?;
+        for (other, votes) in others {
+            if other == *voter {
+                continue;
+            }
+            let root = fixed::sqrt(votes, precision);
+            let pair = Self::pair_key(voter, &other);
+            let old_k = self
+                .pair_overlaps
+                .may_load(deps.storage, (round_id, &pair))?
+                .unwrap_or_default();
+            let new_k = old_k - old_root * root + new_root * root;
+
+            let mut products = self
+                .pair_products
+                .prefix((round_id, &pair))
+                .range(deps.storage, None, None, Order::Ascending)
+                .collect::<StdResult<Vec<_>>>()?;
+            if !products.iter().any(|(id, _)| id == project_id) {
+                products.push((project_id.to_string(), 0));
+            }
+            for (id, product) in products {
+                let new_product = if id == project_id {
+                    new_root * root
+                } else {
+                    product
+                };
+                let area = self
+                    .pairwise_area
+                    .may_load(deps.storage, (round_id, &id))?
+                    .unwrap_or_default()
+                    - term(product, old_k)
+                    + term(new_product, new_k);
+                self.pairwise_area
+                    .save(deps.storage, (round_id, &id), &area)?;
+                if id == project_id {
+                    if new_product == 0 {
+                        self.pair_products
+                            .remove(deps.storage, (round_id, &pair, &id));
+                    } else {
+                        self.pair_products.save(
+                            deps.storage,
+                            (round_id, &pair, &id),
+                            &new_product,
+                        )?;
+                    }
+                }
+            }
+            self.pair_overlaps
+                .save(deps.storage, (round_id, &pair), &new_k)?;
+        }
+        Ok(())
+    }
+
+    /// Votes of voters in the same cluster are summed before the square root, voters without
+    /// a cluster count as a cluster of their own.
+    fn cluster_key(cluster: Option<u64>, voter: &Addr) -> String {
+        match cluster {
+            Some(cluster) => format!("cluster:{}", cluster),
+            None => format!("voter:{}", voter),
+        }
+    }
+
+    /// Keeps cluster-match areas up to date when the votes a cluster gives a project change
+    /// from `old_votes` to `new_votes`.
+    #[allow(clippy::too_many_arguments)]
+    fn update_cluster_area(
+        &self,
+        deps: &mut DepsMut,
+        round_id: &str,
+        project_id: &str,
+        cluster_key: &str,
+        old_votes: u128,
+        new_votes: u128,
+        precision: u32,
+    ) -> StdResult<()> {
+        let old_sum = self
+            .cluster_votes
+            .may_load(deps.storage, (round_id, project_id, cluster_key))?
+            .unwrap_or_default();
+        let new_sum = old_sum - old_votes + new_votes;
+        let area = self
+            .cluster_area
+            .may_load(deps.storage, (round_id, project_id))?
+            .unwrap_or_default()
+            - fixed::sqrt(old_sum, precision)
+            + fixed::sqrt(new_sum, precision);
+
+        if new_sum == 0 {
+            self.cluster_votes
+                .remove(deps.storage, (round_id, project_id, cluster_key));
+        } else {
+            self.cluster_votes
+                .save(deps.storage, (round_id, project_id, cluster_key), &new_sum)?;
+        }
+        self.cluster_area
+            .save(deps.storage, (round_id, project_id), &area)
+    }
+
+    /// Splits `round.fund` proportionally to `areas` among eligible projects. With a capped
+    /// strategy the excess over the cap is shared again among the projects below it.
+    fn allocate(round: &Round, areas: &[u128], eligible: &[bool]) -> Vec<u128> {
+        let cap = match round.matching_strategy {
+            MatchingStrategy::Capped { cap_bps } => {
+                round.fund.multiply_ratio(cap_bps, MAX_CAP_BPS).u128()
+            }
+            _ => u128::MAX,
+        };
+
+        let mut matching = vec![0; areas.len()];
+        let mut active: Vec<usize> = (0..areas.len()).filter(|&i| eligible[i]).collect();
+        let mut fund = round.fund;
+        loop {
+            let total: u128 = active.iter().map(|&i| areas[i]).sum();
+            if total == 0 {
+                break;
+            }
+            let share = |i: usize| fund.multiply_ratio(areas[i], total).u128();
+            let capped: Vec<usize> = active.iter().copied().filter(|&i| share(i) > cap).collect();
+            if capped.is_empty() {
+                for &i in active.iter() {
+                    matching[i] = share(i);
+                }
+                break;
+            }
+            for &i in capped.iter() {
+                matching[i] = cap;
+                fund -= Uint128::from(cap);
+            }
+            active.retain(|i| !capped.contains(i));
+        }
+        matching
+    }
+
+    /// Splits `round.fund` between eligible projects according to the round's matching strategy.
+    /// Ineligible projects keep only their direct contributions.
+    fn compute_distribution(&self, deps: Deps, round: &Round) -> StdResult<Vec<ProjectGrant>> {
+        let mut projects = self
+            .projects
+            .prefix(&round.id.to_string())
+            .range(deps.storage, None, None, Order::Ascending)
+            .map(|item| item.map(|(_, project)| project))
+            .collect::<StdResult<Vec<Project>>>()?;
+        projects.sort_by_key(|project| project.id);
+
+        let stored_areas = match round.matching_strategy {
+            MatchingStrategy::Pairwise { .. } => Some(&self.pairwise_area),
+            MatchingStrategy::Cluster => Some(&self.cluster_area),
+            _ => None,
+        };
+        let areas: Vec<u128> = match stored_areas {
+            Some(stored_areas) => projects
+                .iter()
+                .map(|project| {
+                    stored_areas
+                        .may_load(
+                            deps.storage,
+                            (&round.id.to_string(), &project.id.to_string()),
+                        )
+                        .map(Option::unwrap_or_default)
+                })
+                .collect::<StdResult<_>>()?,
+            None => projects.iter().map(|project| project.area).collect(),
+        };
+        let eligible: Vec<bool> = projects
+            .iter()
+            .map(|project| Self::is_eligible(round, project))
+            .collect();
+        let matching = Self::allocate(round, &areas, &eligible);
+
+        Ok(projects
+            .into_iter()
+            .zip(areas)
+            .zip(eligible.into_iter().zip(matching))
+            .map(|((project, area), (eligible, matching))| ProjectGrant {
+                project_id: project.id,
+                owner: project.owner,
+                area,
+                contribution: project.contribution,
+                voter_count: project.voter_count,
+                eligible,
+                matching,
+                total: project.contribution + matching,
+            })
+            .collect())
+    }
+
+    /// Stores the current distribution so it can be reviewed and, once locked, claimed.
+    fn publish_distribution(
+        &self,
+        deps: &mut DepsMut,
+        round: &Round,
+    ) -> Result<Vec<ProjectGrant>, ContractError> {
+        if !round.snapshot_hash.is_empty() {
+            return Err(ContractError::SnapshotFrozen { round_id: round.id });
+        }
+
+        let grants = self.compute_distribution(deps.as_ref(), round)?;
+        // Merkle rounds pay out off-chain, only the snapshot hash and allocated total are kept
+        if round.payout_mode == PayoutMode::Merkle {
+            return Ok(grants);
+        }
+        for grant in grants.iter() {
+            self.grants.save(
+                deps.storage,
+                (&round.id.to_string(), &grant.project_id.to_string()),
+                grant,
+            )?;
+        }
+        Ok(grants)
+    }
+
+    /// Freezes the distribution of a round. The snapshot hash is the keccak256 of the
+    /// JSON-encoded grants ordered by project id, so auditors can recompute it off-chain.
+    fn finalize_round(
+        &self,
+        deps: &mut DepsMut,
+        env: &Env,
+        round: &mut Round,
+    ) -> Result<Event, ContractError> {
+        if round.deposited < round.fund.u128() {
+            return Err(ContractError::RoundNotFunded {
+                round_id: round.id,
+                fund: round.fund.u128(),
+                deposited: round.deposited,
+            });
+        }
+        let grants = self.publish_distribution(deps, round)?;
+
+        round.status = RoundStatus::Finished;
+        round.finalized_at = env.block.time.seconds();
+        round.snapshot_hash = hex::encode(hash::keccak256(&to_json_vec(&grants)?));
+        round.allocated = grants.iter().map(|grant| grant.matching).sum();
+        self.rounds
+            .save(deps.storage, &round.id.to_string(), round)?;
+
+        Ok(Event::new("finalize_round")
+            .add_attribute("round_id", round.id.to_string())
+            .add_attribute("projects", grants.len().to_string())
+            .add_attribute("snapshot_hash", round.snapshot_hash.clone()))
+    }
+
+    /// Subtraction of recorded amounts, an underflow means the records disagree.
+    fn checked_sub(value: u128, amount: u128) -> Result<u128, ContractError> {
+        Ok(Uint128::from(value)
+            .checked_sub(Uint128::from(amount))
+            .map_err(StdError::from)?
+            .u128())
+    }
+
+    /// Books matching leaving the contract, which can never exceed what was deposited for the round.
+    fn release_matching(round: &mut Round, amount: u128) -> Result<(), ContractError> {
+        if round.matching_paid + amount > round.deposited {
+            return Err(ContractError::MatchingExceedsDeposit {
+                round_id: round.id,
+                deposited: round.deposited,
+            });
+        }
+        round.matching_paid += amount;
+        Ok(())
+    }
+
+    /// Matching released linearly from finalization, nothing before the cliff.
+    fn vested_amount(round: &Round, matching: u128, at: u64) -> u128 {
+        let elapsed = at.saturating_sub(round.finalized_at);
+        if elapsed < round.vesting_cliff {
+            0
+        } else if elapsed >= round.vesting_duration {
+            matching
+        } else {
+            Uint128::from(matching)
+                .multiply_ratio(elapsed, round.vesting_duration)
+                .u128()
+        }
+    }
+
+    fn milestone_released(matching: u128, milestones: &[Milestone]) -> u128 {
+        let percentage: u64 = milestones
+            .iter()
+            .filter(|milestone| milestone.completed)
+            .map(|milestone| milestone.percentage)
+            .sum();
+        Uint128::from(matching)
+            .multiply_ratio(percentage, 100u64)
+            .u128()
+    }
+
+    /// Loads the grant of a milestone round whose plan is still open.
+    fn load_milestone_grant(
+        &self,
+        deps: &DepsMut,
+        round: &Round,
+        project_id: u64,
+    ) -> Result<ProjectGrant, ContractError> {
+        if round.payout_mode != PayoutMode::Milestone {
+            return Err(ContractError::WrongPayoutMode { round_id: round.id });
+        }
+        let key = (round.id.to_string(), project_id.to_string());
+        let grant = self
+            .grants
+            .may_load(deps.storage, (&key.0, &key.1))?
+            .ok_or(ContractError::ProjectNotExist { project_id })?;
+        if self.milestone_returns.has(deps.storage, (&key.0, &key.1)) {
+            return Err(ContractError::MilestonesClosed { project_id });
+        }
+        Ok(grant)
+    }
+
+    /// Adds matching left over in `round` to the fund of a later round still in voting.
+    fn fund_next_round(
+        &self,
+        deps: &mut DepsMut,
+        round: &Round,
+        next_round_id: u64,
+        amount: u128,
+    ) -> Result<(), ContractError> {
+        let mut next_round = self.rounds.load(deps.storage, &next_round_id.to_string())?;
+        if next_round_id <= round.id || next_round.status != RoundStatus::Voting {
+            return Err(ContractError::RoundNotInVoting {
+                round_id: next_round_id,
+            });
+        }
+        if next_round.donation_denom != round.donation_denom {
+            return Err(ContractError::InvalidDenom {
+                denom: next_round.donation_denom,
             });
         }
+        next_round.fund += Uint128::from(amount);
+        next_round.deposited += amount;
+        next_round.rolled_in += amount;
+        self.rounds
+            .save(deps.storage, &next_round_id.to_string(), &next_round)?;
         Ok(())
     }
 
+    fn decode_hash(value: &str) -> Result<[u8; 32], ContractError> {
+        hex::decode(value)
+            .ok()
+            .and_then(|bytes| bytes.try_into().ok())
+            .ok_or(ContractError::InvalidMerkleProof)
+    }
+
     #[msg(instantiate)]
     pub fn instantiate(
         &self,
@@ -82,11 +904,17 @@ impl QGContract<'_> {
         }
 
         self.owner.save(deps.storage, &msg.sender)?;
+        self.treasury.save(deps.storage, &msg.sender)?;
+        self.fee_collector.save(deps.storage, &msg.sender)?;
+        // The initial admins start with every role, the owner narrows them down from there
         for admin in admins.clone() {
             let admin = deps.api.addr_validate(&admin)?;
             self.admins.save(deps.storage, &admin, &Empty {})?;
+            self.roles.save(deps.storage, &admin, &ALL_ROLES.to_vec())?;
         }
         self.current_round.save(deps.storage, &0)?;
+        self.deposits_tracked.save(deps.storage, &true)?;
+        cw2::set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
         Ok(Response::new()
             .add_attribute("action", "instantiate")
             .add_event(
@@ -96,6 +924,74 @@ impl QGContract<'_> {
             ))
     }
 
+    /// With `precision`, it becomes the default for new rounds and areas of rounds still in
+    /// voting are rescaled to it.
+    /// Rounds stored before deposits were tracked count their whole fund as deposited.
+    /// Admins of deployments from before versioning are granted every role.
+    #[msg(migrate)]
+    pub fn migrate(
+        &self,
+        ctx: (DepsMut, Env),
+        precision: Option<u32>,
+    ) -> Result<Response, ContractError> {
+        let (deps, _) = ctx;
+
+        if let Some(precision) = precision {
+            if precision == 0 || precision > MAX_PRECISION {
+                return Err(ContractError::InvalidPrecision { precision });
+            }
+            self.precision.save(deps.storage, &precision)?;
+        }
+
+        // Admins of deployments from before versioning used to hold every role implicitly
+        if cw2::get_contract_version(deps.storage).is_err() {
+            let admins = self
+                .admins
+                .keys(deps.storage, None, None, Order::Ascending)
+                .collect::<StdResult<Vec<_>>>()?;
+            for admin in admins {
+                self.roles.save(deps.storage, &admin, &ALL_ROLES.to_vec())?;
+            }
+        }
+
+        let deposits_tracked = self
+            .deposits_tracked
+            .may_load(deps.storage)?
+            .unwrap_or_default();
+        let rounds = self
+            .rounds
+            .range(deps.storage, None, None, Order::Ascending)
+            .collect::<StdResult<Vec<_>>>()?;
+        let mut rescaled = 0;
+        for (round_id, mut round) in rounds {
+            if !deposits_tracked {
+                round.deposited = round.fund.u128();
+            }
+            let target = precision.unwrap_or(round.precision);
+            if round.status == RoundStatus::Voting && target > round.precision {
+                self.rescale_areas(deps.storage, &mut round, target)?;
+                rescaled += 1;
+            }
+            self.rounds.save(deps.storage, &round_id, &round)?;
+        }
+        self.deposits_tracked.save(deps.storage, &true)?;
+        cw2::set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
+
+        Ok(Response::new()
+            .add_attribute("action", "migrate")
+            .add_event(
+                Event::new("migrate")
+                    .add_attribute(
+                        "precision",
+                        self.precision
+                            .may_load(deps.storage)?
+                            .unwrap_or(LEGACY_PRECISION)
+                            .to_string(),
+                    )
+                    .add_attribute("rescaled_rounds", rescaled.to_string()),
+            ))
+    }
+
     // ============= Query ============= //
     #[msg(query)]
     pub fn admin_list(&self, ctx: (Deps, Env)) -> StdResult<AdminListResp> {
@@ -110,6 +1006,113 @@ impl QGContract<'_> {
         Ok(AdminListResp { admins: admins? })
     }
 
+    #[msg(query)]
+    pub fn treasury(&self, ctx: (Deps, Env)) -> StdResult<TreasuryResp> {
+        let (deps, _) = ctx;
+
+        let treasury = match self.treasury.may_load(deps.storage)? {
+            Some(treasury) => treasury,
+            None => self.owner.load(deps.storage)?,
+        };
+
+        Ok(TreasuryResp {
+            treasury: treasury.to_string(),
+        })
+    }
+
+    #[msg(query)]
+    pub fn fee_collector(&self, ctx: (Deps, Env)) -> StdResult<FeeCollectorResp> {
+        let (deps, _) = ctx;
+
+        let fee_collector = match self.fee_collector.may_load(deps.storage)? {
+            Some(fee_collector) => fee_collector,
+            None => self.owner.load(deps.storage)?,
+        };
+
+        Ok(FeeCollectorResp {
+            fee_collector: fee_collector.to_string(),
+        })
+    }
+
+    #[msg(query)]
+    pub fn queued_actions(
+        &self,
+        ctx: (Deps, Env),
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    ) -> StdResult<QueuedActionsResp> {
+        let (deps, _) = ctx;
+
+        let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+        let actions = self
+            .queued_actions
+            .range(
+                deps.storage,
+                start_after.map(Bound::exclusive),
+                None,
+                Order::Ascending,
+            )
+            .take(limit)
+            .map(|item| item.map(|(_, action)| action))
+            .collect::<StdResult<_>>()?;
+
+        Ok(QueuedActionsResp {
+            delay: self
+                .timelock_delay
+                .may_load(deps.storage)?
+                .unwrap_or_default(),
+            actions,
+        })
+    }
+
+    #[msg(query)]
+    pub fn proposals(
+        &self,
+        ctx: (Deps, Env),
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    ) -> StdResult<ProposalsResp> {
+        let (deps, _) = ctx;
+
+        let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+        let proposals = self
+            .proposals
+            .range(
+                deps.storage,
+                start_after.map(Bound::exclusive),
+                None,
+                Order::Ascending,
+            )
+            .filter(|item| !matches!(item, Ok((_, proposal)) if proposal.executed))
+            .take(limit)
+            .map(|item| item.map(|(_, proposal)| proposal))
+            .collect::<StdResult<_>>()?;
+
+        Ok(ProposalsResp {
+            threshold: self
+                .approval_threshold
+                .may_load(deps.storage)?
+                .unwrap_or_default(),
+            proposals,
+        })
+    }
+
+    #[msg(query)]
+    pub fn roles_of(&self, ctx: (Deps, Env), address: String) -> StdResult<RolesResp> {
+        let (deps, _) = ctx;
+
+        let address = deps.api.addr_validate(&address)?;
+        let roles = self
+            .roles
+            .may_load(deps.storage, &address)?
+            .unwrap_or_default();
+
+        Ok(RolesResp {
+            is_admin: self.admins.has(deps.storage, &address),
+            roles,
+        })
+    }
+
     #[msg(query)]
     pub fn round(&self, ctx: (Deps, Env), round_id: u64) -> StdResult<Round> {
         let (deps, _) = ctx;
@@ -137,450 +1140,3337 @@ impl QGContract<'_> {
         }
     }
 
-    // ============= Execute ============= //
-    #[msg(exec)]
-    pub fn add_admin(
+    #[msg(query)]
+    pub fn pause_state(
         &self,
-        ctx: (DepsMut, Env, MessageInfo),
-        admin: String,
-    ) -> Result<Response, ContractError> {
-        let (deps, _, info) = ctx;
+        ctx: (Deps, Env),
+        round_id: Option<u64>,
+    ) -> StdResult<PauseStateResp> {
+        let (deps, env) = ctx;
+
+        let global = self.paused.may_load(deps.storage)?.unwrap_or_default();
+        let round = round_id
+            .map(|round_id| self.round((deps, env), round_id).map(|round| round.paused))
+            .transpose()?;
+
+        Ok(PauseStateResp { global, round })
+    }
+
+    #[msg(query)]
+    pub fn round_stats(
+        &self,
+        ctx: (Deps, Env),
+        round_id: u64,
+        top_n: Option<u32>,
+    ) -> StdResult<RoundStatsResp> {
+        let (deps, env) = ctx;
+
+        let round = self.round((deps, env), round_id)?;
+        let top_n = top_n.unwrap_or(DEFAULT_TOP_N).min(MAX_LIMIT) as usize;
+
+        let top = |sort_by| -> StdResult<Vec<Project>> {
+            let mut projects = self.sorted_projects(deps, round_id, sort_by)?;
+            projects.truncate(top_n);
+            Ok(projects)
+        };
+
+        Ok(RoundStatsResp {
+            round_id,
+            voter_count: round.voter_count,
+            project_number: round.project_number,
+            total_contributions: round.total_amounts,
+            total_area: round.total_area,
+            top_by_area: top(ProjectSort::Area)?,
+            top_by_contribution: top(ProjectSort::Contribution)?,
+            top_by_votes: top(ProjectSort::Votes)?,
+        })
+    }
+
+    #[msg(query)]
+    pub fn leaderboard(
+        &self,
+        ctx: (Deps, Env),
+        round_id: u64,
+        sort_by: Option<ProjectSort>,
+        start_after: Option<u32>,
+        limit: Option<u32>,
+    ) -> StdResult<LeaderboardResp> {
+        let (deps, env) = ctx;
+
+        self.round((deps, env), round_id)?;
+        let start_after = start_after.unwrap_or(0);
+        let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT);
+
+        let entries = self
+            .sorted_projects(deps, round_id, sort_by.unwrap_or_default())?
+            .into_iter()
+            .zip(1u32..)
+            .skip(start_after as usize)
+            .take(limit as usize)
+            .map(|(project, rank)| LeaderboardEntry { rank, project })
+            .collect();
+
+        Ok(LeaderboardResp { entries })
+    }
+
+    #[msg(query)]
+    pub fn round_voters(
+        &self,
+        ctx: (Deps, Env),
+        round_id: u64,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    ) -> StdResult<VotersResp> {
+        let (deps, env) = ctx;
+
+        let round = self.round((deps, env), round_id)?;
+        let start_after = start_after
+            .map(|addr| deps.api.addr_validate(&addr))
+            .transpose()?;
+        let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+
+        let voters = self
+            .voters
+            .prefix(&round_id.to_string())
+            .range(
+                deps.storage,
+                start_after.as_ref().map(Bound::exclusive),
+                None,
+                Order::Ascending,
+            )
+            .take(limit)
+            .map(|item| {
+                item.map(|(address, amount)| VoterEntry {
+                    address: address.to_string(),
+                    amount,
+                })
+            })
+            .collect::<StdResult<_>>()?;
+
+        Ok(VotersResp {
+            voter_count: round.voter_count,
+            voters,
+        })
+    }
+
+    #[msg(query)]
+    pub fn project_voters(
+        &self,
+        ctx: (Deps, Env),
+        round_id: u64,
+        project_id: u64,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    ) -> StdResult<VotersResp> {
+        let (deps, env) = ctx;
+
+        let project = self.project((deps, env), round_id, project_id)?;
+        let start_after = start_after
+            .map(|addr| deps.api.addr_validate(&addr))
+            .transpose()?;
+        let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+
+        let voters = self
+            .votes
+            .prefix((&round_id.to_string(), &project_id.to_string()))
+            .range(
+                deps.storage,
+                start_after.as_ref().map(Bound::exclusive),
+                None,
+                Order::Ascending,
+            )
+            .take(limit)
+            .map(|item| {
+                item.map(|(address, votes)| VoterEntry {
+                    address: address.to_string(),
+                    amount: votes,
+                })
+            })
+            .collect::<StdResult<_>>()?;
+
+        Ok(VotersResp {
+            voter_count: project.voter_count,
+            voters,
+        })
+    }
+
+    #[msg(query)]
+    pub fn grant(&self, ctx: (Deps, Env), round_id: u64, project_id: u64) -> StdResult<GrantResp> {
+        let (deps, _) = ctx;
+
+        let grant = self.grants.may_load(
+            deps.storage,
+            (&round_id.to_string(), &project_id.to_string()),
+        )?;
+        let claimed = self.claims.may_load(
+            deps.storage,
+            (&round_id.to_string(), &project_id.to_string()),
+        )?;
+
+        match grant {
+            Some(grant) => Ok(GrantResp {
+                grant,
+                claimed: claimed.unwrap_or_default(),
+            }),
+            None => Err(StdError::generic_err("Grant not found")),
+        }
+    }
+
+    #[msg(query)]
+    pub fn snapshot(
+        &self,
+        ctx: (Deps, Env),
+        round_id: u64,
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    ) -> StdResult<SnapshotResp> {
+        let (deps, env) = ctx;
+
+        let round = self.round((deps, env), round_id)?;
+        let start_after = start_after.unwrap_or_default();
+        let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+
+        // Merkle rounds store no grants, their frozen distribution is recomputed instead
+        let mut grants = match round.payout_mode {
+            PayoutMode::Merkle if round.snapshot_hash.is_empty() => vec![],
+            PayoutMode::Merkle => self.compute_distribution(deps, &round)?,
+            _ => self
+                .grants
+                .prefix(&round_id.to_string())
+                .range(deps.storage, None, None, Order::Ascending)
+                .map(|item| item.map(|(_, grant)| grant))
+                .collect::<StdResult<Vec<ProjectGrant>>>()?,
+        };
+        grants.sort_by_key(|grant| grant.project_id);
+        grants.retain(|grant| grant.project_id > start_after);
+        grants.truncate(limit);
+
+        Ok(SnapshotResp {
+            round_id,
+            finalized_at: round.finalized_at,
+            snapshot_hash: round.snapshot_hash,
+            grants,
+        })
+    }
+
+    #[msg(query)]
+    pub fn pairwise_areas(
+        &self,
+        ctx: (Deps, Env),
+        round_id: u64,
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    ) -> StdResult<PairwiseAreasResp> {
+        let (deps, env) = ctx;
+
+        let round = self.round((deps, env), round_id)?;
+        let threshold = match round.matching_strategy {
+            MatchingStrategy::Pairwise { threshold } => threshold,
+            _ => 0,
+        };
+        let start_after = start_after.unwrap_or_default();
+        let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+
+        let areas = (start_after + 1..=round.project_number)
+            .take(limit)
+            .map(|project_id| {
+                let area = self
+                    .pairwise_area
+                    .may_load(
+                        deps.storage,
+                        (&round_id.to_string(), &project_id.to_string()),
+                    )?
+                    .unwrap_or_default();
+                Ok(ProjectArea { project_id, area })
+            })
+            .collect::<StdResult<Vec<_>>>()?;
+
+        Ok(PairwiseAreasResp {
+            round_id,
+            threshold,
+            areas,
+        })
+    }
+
+    #[msg(query)]
+    pub fn vesting(
+        &self,
+        ctx: (Deps, Env),
+        round_id: u64,
+        project_id: u64,
+    ) -> StdResult<VestingResp> {
+        let (deps, env) = ctx;
+
+        let round = self.rounds.load(deps.storage, &round_id.to_string())?;
+        let key = (round_id.to_string(), project_id.to_string());
+        let matching = self
+            .grants
+            .may_load(deps.storage, (&key.0, &key.1))?
+            .map(|grant| grant.matching)
+            .unwrap_or_default();
+        let claimed = self
+            .claims
+            .may_load(deps.storage, (&key.0, &key.1))?
+            .unwrap_or_default();
+        let halted_at = self
+            .vesting_halts
+            .may_load(deps.storage, (&key.0, &key.1))?;
+        let vested = if round.payout_mode == PayoutMode::Vested && round.finalized_at > 0 {
+            let at = halted_at.unwrap_or(env.block.time.seconds());
+            Self::vested_amount(&round, matching, at)
+        } else {
+            0
+        };
+
+        Ok(VestingResp {
+            round_id,
+            project_id,
+            matching,
+            vested,
+            claimed,
+            releasable: vested.saturating_sub(claimed),
+            halted_at,
+        })
+    }
+
+    #[msg(query)]
+    pub fn milestones(
+        &self,
+        ctx: (Deps, Env),
+        round_id: u64,
+        project_id: u64,
+    ) -> StdResult<MilestonesResp> {
+        let (deps, _) = ctx;
+
+        let key = (round_id.to_string(), project_id.to_string());
+        let matching = self
+            .grants
+            .may_load(deps.storage, (&key.0, &key.1))?
+            .map(|grant| grant.matching)
+            .unwrap_or_default();
+        let milestones = self
+            .milestones
+            .may_load(deps.storage, (&key.0, &key.1))?
+            .unwrap_or_default();
+        let claimed = self
+            .claims
+            .may_load(deps.storage, (&key.0, &key.1))?
+            .unwrap_or_default();
+
+        Ok(MilestonesResp {
+            round_id,
+            project_id,
+            matching,
+            released: Self::milestone_released(matching, &milestones),
+            claimed,
+            returned: self
+                .milestone_returns
+                .may_load(deps.storage, (&key.0, &key.1))?,
+            milestones,
+        })
+    }
+
+    #[msg(query)]
+    pub fn voter_weight(
+        &self,
+        ctx: (Deps, Env),
+        round_id: u64,
+        voter: String,
+    ) -> StdResult<VoterWeightResp> {
+        let (deps, _) = ctx;
+
+        let round = self.rounds.load(deps.storage, &round_id.to_string())?;
+        let addr = deps.api.addr_validate(&voter)?;
+        let weight = self
+            .voter_weights
+            .may_load(deps.storage, (&round_id.to_string(), &addr))?;
+
+        Ok(VoterWeightResp {
+            round_id,
+            voter,
+            policy: round.weight_policy,
+            weight,
+        })
+    }
+
+    #[msg(query)]
+    pub fn merkle_claim(
+        &self,
+        ctx: (Deps, Env),
+        round_id: u64,
+        index: u64,
+    ) -> StdResult<MerkleClaimResp> {
+        let (deps, _) = ctx;
+
+        let word = self
+            .merkle_claims
+            .may_load(deps.storage, (&round_id.to_string(), index / 64))?
+            .unwrap_or_default();
+
+        Ok(MerkleClaimResp {
+            index,
+            claimed: word & (1u64 << (index % 64)) != 0,
+        })
+    }
+
+    #[msg(query)]
+    pub fn distribution(&self, ctx: (Deps, Env), round_id: u64) -> StdResult<DistributionResp> {
+        let (deps, env) = ctx;
+
+        let round = self.round((deps, env), round_id)?;
+        let grants = self.compute_distribution(deps, &round)?;
+        let eligible_area = grants
+            .iter()
+            .filter(|grant| grant.eligible)
+            .map(|grant| grant.area)
+            .sum();
+
+        Ok(DistributionResp {
+            round_id,
+            fund: round.fund.u128(),
+            eligible_area,
+            grants,
+        })
+    }
+
+    // ============= Execute ============= //
+    /// Admins only approve proposals, the owner grants them roles separately.
+    #[msg(exec)]
+    pub fn add_admin(
+        &self,
+        ctx: (DepsMut, Env, MessageInfo),
+        admin: String,
+    ) -> Result<Response, ContractError> {
+        let (deps, _, info) = ctx;
+        self.check_owner_permission(&deps, &info)?;
+
+        let admin = deps.api.addr_validate(&admin)?;
+        if self.admins.has(deps.storage, &admin) {
+            return Err(ContractError::NoDupAddress { address: admin });
+        }
+
+        self.admins.save(deps.storage, &admin, &Empty {})?;
+
+        let resp = Response::new()
+            .add_attribute("action", "add_member")
+            .add_event(Event::new("admin_added").add_attribute("addr", admin));
+        Ok(resp)
+    }
+
+    #[msg(exec)]
+    pub fn del_admin(
+        &self,
+        ctx: (DepsMut, Env, MessageInfo),
+        admin: String,
+    ) -> Result<Response, ContractError> {
+        let (mut deps, env, info) = ctx;
+        self.check_owner_permission(&deps, &info)?;
+
+        let action = AdminAction::DelAdmin { admin };
+        match self.queue_action(&mut deps, &env, &info, action.clone())? {
+            Some(resp) => Ok(resp),
+            None => self.run_action(deps, env, action),
+        }
+    }
+
+    fn apply_del_admin(&self, deps: DepsMut, admin: String) -> Result<Response, ContractError> {
+        let admin = deps.api.addr_validate(&admin)?;
+        if !self.admins.has(deps.storage, &admin) {
+            return Err(ContractError::NoAdmins);
+        }
+        // Pending proposals must stay approvable by the remaining admins
+        let threshold = self
+            .approval_threshold
+            .may_load(deps.storage)?
+            .unwrap_or_default();
+        let admins = self
+            .admins
+            .keys(deps.storage, None, None, Order::Ascending)
+            .count() as u64
+            - 1;
+        if threshold > admins {
+            return Err(ContractError::InvalidThreshold { threshold, admins });
+        }
+
+        self.admins.remove(deps.storage, &admin);
+        self.roles.remove(deps.storage, &admin);
+
+        let resp = Response::new()
+            .add_attribute("action", "del_admin")
+            .add_event(Event::new("admin_removed").add_attribute("addr", admin));
+        Ok(resp)
+    }
+
+    #[msg(exec)]
+    pub fn set_treasury(
+        &self,
+        ctx: (DepsMut, Env, MessageInfo),
+        treasury: String,
+    ) -> Result<Response, ContractError> {
+        let (deps, _, info) = ctx;
+        self.check_owner_permission(&deps, &info)?;
+
+        let treasury = deps.api.addr_validate(&treasury)?;
+        self.treasury.save(deps.storage, &treasury)?;
+
+        let resp = Response::new()
+            .add_attribute("action", "set_treasury")
+            .add_event(Event::new("set_treasury").add_attribute("addr", treasury));
+        Ok(resp)
+    }
+
+    #[msg(exec)]
+    pub fn set_fee_collector(
+        &self,
+        ctx: (DepsMut, Env, MessageInfo),
+        fee_collector: String,
+    ) -> Result<Response, ContractError> {
+        let (deps, _, info) = ctx;
+        self.check_owner_permission(&deps, &info)?;
+
+        let fee_collector = deps.api.addr_validate(&fee_collector)?;
+        self.fee_collector.save(deps.storage, &fee_collector)?;
+
+        let resp = Response::new()
+            .add_attribute("action", "set_fee_collector")
+            .add_event(Event::new("set_fee_collector").add_attribute("addr", fee_collector));
+        Ok(resp)
+    }
+
+    #[msg(exec)]
+    pub fn set_timelock(
+        &self,
+        ctx: (DepsMut, Env, MessageInfo),
+        delay: u64,
+    ) -> Result<Response, ContractError> {
+        let (deps, _, info) = ctx;
+        self.check_owner_permission(&deps, &info)?;
+
+        self.timelock_delay.save(deps.storage, &delay)?;
+
+        let resp = Response::new()
+            .add_attribute("action", "set_timelock")
+            .add_event(Event::new("set_timelock").add_attribute("delay", delay.to_string()));
+        Ok(resp)
+    }
+
+    /// Sets the decimals of weights and areas for rounds started from now on.
+    #[msg(exec)]
+    pub fn set_precision(
+        &self,
+        ctx: (DepsMut, Env, MessageInfo),
+        precision: u32,
+    ) -> Result<Response, ContractError> {
+        let (deps, _, info) = ctx;
+        self.check_owner_permission(&deps, &info)?;
+
+        if precision == 0 || precision > MAX_PRECISION {
+            return Err(ContractError::InvalidPrecision { precision });
+        }
+        self.precision.save(deps.storage, &precision)?;
+
+        let resp = Response::new()
+            .add_attribute("action", "set_precision")
+            .add_event(
+                Event::new("set_precision").add_attribute("precision", precision.to_string()),
+            );
+        Ok(resp)
+    }
+
+    #[msg(exec)]
+    pub fn execute_action(
+        &self,
+        ctx: (DepsMut, Env, MessageInfo),
+        id: u64,
+    ) -> Result<Response, ContractError> {
+        let (deps, env, info) = ctx;
+
+        let queued = self
+            .queued_actions
+            .may_load(deps.storage, id)?
+            .ok_or(ContractError::QueuedActionNotExist { id })?;
+        self.check_action_permission(&deps, &info, &queued.action)?;
+
+        if env.block.time.seconds() < queued.eta {
+            return Err(ContractError::TimelockNotExpired {
+                id,
+                eta: queued.eta,
+            });
+        }
+
+        self.queued_actions.remove(deps.storage, id);
+        let resp = self.run_action(deps, env, queued.action)?;
+        Ok(resp.add_event(Event::new("action_executed").add_attribute("id", id.to_string())))
+    }
+
+    #[msg(exec)]
+    pub fn cancel_action(
+        &self,
+        ctx: (DepsMut, Env, MessageInfo),
+        id: u64,
+    ) -> Result<Response, ContractError> {
+        let (deps, _, info) = ctx;
+        self.check_owner_permission(&deps, &info)?;
+
+        if !self.queued_actions.has(deps.storage, id) {
+            return Err(ContractError::QueuedActionNotExist { id });
+        }
+        self.queued_actions.remove(deps.storage, id);
+
+        let resp = Response::new()
+            .add_attribute("action", "cancel_action")
+            .add_event(Event::new("action_cancelled").add_attribute("id", id.to_string()));
+        Ok(resp)
+    }
+
+    #[msg(exec)]
+    pub fn set_approval_threshold(
+        &self,
+        ctx: (DepsMut, Env, MessageInfo),
+        threshold: u64,
+    ) -> Result<Response, ContractError> {
+        let (deps, _, info) = ctx;
         self.check_owner_permission(&deps, &info)?;
 
-        let admin = deps.api.addr_validate(&admin)?;
-        if self.admins.has(deps.storage, &admin) {
-            return Err(ContractError::NoDupAddress { address: admin });
+        let admins = self
+            .admins
+            .keys(deps.storage, None, None, Order::Ascending)
+            .count() as u64;
+        if threshold == 0 || threshold > admins {
+            return Err(ContractError::InvalidThreshold { threshold, admins });
+        }
+        self.approval_threshold.save(deps.storage, &threshold)?;
+
+        let resp = Response::new()
+            .add_attribute("action", "set_approval_threshold")
+            .add_event(
+                Event::new("set_approval_threshold")
+                    .add_attribute("threshold", threshold.to_string()),
+            );
+        Ok(resp)
+    }
+
+    #[msg(exec)]
+    pub fn propose(
+        &self,
+        ctx: (DepsMut, Env, MessageInfo),
+        action: ProposalAction,
+    ) -> Result<Response, ContractError> {
+        let (mut deps, _, info) = ctx;
+        if !self.admins.has(deps.storage, &info.sender) {
+            return Err(ContractError::Unauthorized {
+                sender: info.sender,
+            });
+        }
+
+        let id = self
+            .next_proposal_id
+            .may_load(deps.storage)?
+            .unwrap_or_default()
+            + 1;
+        self.next_proposal_id.save(deps.storage, &id)?;
+
+        let proposal = Proposal {
+            id,
+            action,
+            proposer: info.sender.to_string(),
+            approvals: vec![],
+            executed: false,
+        };
+        self.proposals.save(deps.storage, id, &proposal)?;
+
+        // The proposer's approval is implied
+        let resp = self.approve_proposal(&mut deps, &info, id)?;
+        Ok(resp.add_event(
+            Event::new("proposal_created")
+                .add_attribute("id", id.to_string())
+                .add_attribute("proposer", info.sender),
+        ))
+    }
+
+    #[msg(exec)]
+    pub fn approve(
+        &self,
+        ctx: (DepsMut, Env, MessageInfo),
+        id: u64,
+    ) -> Result<Response, ContractError> {
+        let (mut deps, _, info) = ctx;
+        if !self.admins.has(deps.storage, &info.sender) {
+            return Err(ContractError::Unauthorized {
+                sender: info.sender,
+            });
+        }
+
+        self.approve_proposal(&mut deps, &info, id)
+    }
+
+    /// Runs a proposal once enough current admins approved it. A failing action leaves the
+    /// proposal pending so it can be executed later.
+    #[msg(exec)]
+    pub fn execute_proposal(
+        &self,
+        ctx: (DepsMut, Env, MessageInfo),
+        id: u64,
+    ) -> Result<Response, ContractError> {
+        let (mut deps, env, info) = ctx;
+        if !self.admins.has(deps.storage, &info.sender) {
+            return Err(ContractError::Unauthorized {
+                sender: info.sender,
+            });
+        }
+
+        let mut proposal = self.load_pending_proposal(&deps, id)?;
+        let threshold = self
+            .approval_threshold
+            .may_load(deps.storage)?
+            .unwrap_or_default();
+        let approvals = self.count_approvals(&deps, &proposal)?;
+        if approvals < threshold {
+            return Err(ContractError::NotEnoughApprovals {
+                id,
+                approvals,
+                threshold,
+            });
+        }
+        proposal.executed = true;
+        self.proposals.save(deps.storage, id, &proposal)?;
+
+        // The approvals stand in for the role checks of the direct calls
+        let resp = match proposal.action {
+            ProposalAction::EndRound { round_id } => self.apply_end_round(deps, env, round_id)?,
+            ProposalAction::Withdraw { round_id, amount } => {
+                // Withdrawals still wait out the timelock once approved
+                let action = AdminAction::Withdraw { round_id, amount };
+                match self.queue_action(&mut deps, &env, &info, action.clone())? {
+                    Some(resp) => resp,
+                    None => self.run_action(deps, env, action)?,
+                }
+            }
+            ProposalAction::SweepUnclaimed { round_id } => {
+                self.apply_sweep_unclaimed(deps, env, round_id)?
+            }
+            ProposalAction::CloseMilestones {
+                round_id,
+                project_id,
+                next_round_id,
+            } => self.apply_close_milestones(deps, env, round_id, project_id, next_round_id)?,
+            ProposalAction::HaltVesting {
+                round_id,
+                project_id,
+            } => self.apply_halt_vesting(deps, env, round_id, project_id)?,
+            ProposalAction::Rollover {
+                round_id,
+                next_round_id,
+            } => self.apply_rollover(deps, env, round_id, next_round_id)?,
+            ProposalAction::SetMerkleRoot {
+                round_id,
+                merkle_root,
+                total_amount,
+                claim_deadline,
+            } => self.apply_set_merkle_root(
+                deps,
+                env,
+                round_id,
+                merkle_root,
+                total_amount,
+                claim_deadline,
+            )?,
+        };
+        Ok(resp.add_event(
+            Event::new("proposal_executed")
+                .add_attribute("id", id.to_string())
+                .add_attribute("executor", info.sender),
+        ))
+    }
+
+    fn load_pending_proposal(&self, deps: &DepsMut, id: u64) -> Result<Proposal, ContractError> {
+        let proposal = self
+            .proposals
+            .may_load(deps.storage, id)?
+            .ok_or(ContractError::ProposalNotExist { id })?;
+        if proposal.executed {
+            return Err(ContractError::ProposalExecuted { id });
+        }
+        Ok(proposal)
+    }
+
+    /// Approvals of addresses that are no longer admins do not count.
+    fn count_approvals(&self, deps: &DepsMut, proposal: &Proposal) -> StdResult<u64> {
+        let mut approvals = 0;
+        for approver in &proposal.approvals {
+            if self.admins.has(deps.storage, &Addr::unchecked(approver)) {
+                approvals += 1;
+            }
+        }
+        Ok(approvals)
+    }
+
+    /// Records an approval, the proposal is run separately with `execute_proposal`.
+    fn approve_proposal(
+        &self,
+        deps: &mut DepsMut,
+        info: &MessageInfo,
+        id: u64,
+    ) -> Result<Response, ContractError> {
+        let mut proposal = self.load_pending_proposal(deps, id)?;
+        if proposal.approvals.contains(&info.sender.to_string()) {
+            return Err(ContractError::AlreadyApproved {
+                id,
+                address: info.sender.clone(),
+            });
+        }
+        proposal.approvals.push(info.sender.to_string());
+        self.proposals.save(deps.storage, id, &proposal)?;
+        let approvals = self.count_approvals(deps, &proposal)?;
+
+        Ok(Response::new().add_event(
+            Event::new("proposal_approved")
+                .add_attribute("id", id.to_string())
+                .add_attribute("approver", info.sender.as_str())
+                .add_attribute("approvals", approvals.to_string()),
+        ))
+    }
+
+    #[msg(exec)]
+    pub fn grant_role(
+        &self,
+        ctx: (DepsMut, Env, MessageInfo),
+        address: String,
+        role: Role,
+    ) -> Result<Response, ContractError> {
+        let (deps, _, info) = ctx;
+        self.check_owner_permission(&deps, &info)?;
+
+        let address = deps.api.addr_validate(&address)?;
+        let mut roles = self
+            .roles
+            .may_load(deps.storage, &address)?
+            .unwrap_or_default();
+        if roles.contains(&role) {
+            return Err(ContractError::RoleAlreadyGranted { address, role });
+        }
+        roles.push(role);
+        self.roles.save(deps.storage, &address, &roles)?;
+
+        let resp = Response::new()
+            .add_attribute("action", "grant_role")
+            .add_event(
+                Event::new("role_granted")
+                    .add_attribute("addr", address)
+                    .add_attribute("role", role.to_string()),
+            );
+        Ok(resp)
+    }
+
+    #[msg(exec)]
+    pub fn revoke_role(
+        &self,
+        ctx: (DepsMut, Env, MessageInfo),
+        address: String,
+        role: Role,
+    ) -> Result<Response, ContractError> {
+        let (deps, _, info) = ctx;
+        self.check_owner_permission(&deps, &info)?;
+
+        let address = deps.api.addr_validate(&address)?;
+        let mut roles = self
+            .roles
+            .may_load(deps.storage, &address)?
+            .unwrap_or_default();
+        if !roles.contains(&role) {
+            return Err(ContractError::RoleNotGranted { address, role });
+        }
+        roles.retain(|r| *r != role);
+        if roles.is_empty() {
+            self.roles.remove(deps.storage, &address);
+        } else {
+            self.roles.save(deps.storage, &address, &roles)?;
+        }
+
+        let resp = Response::new()
+            .add_attribute("action", "revoke_role")
+            .add_event(
+                Event::new("role_revoked")
+                    .add_attribute("addr", address)
+                    .add_attribute("role", role.to_string()),
+            );
+        Ok(resp)
+    }
+
+    #[msg(exec)]
+    pub fn pause(
+        &self,
+        ctx: (DepsMut, Env, MessageInfo),
+        round_id: Option<u64>,
+    ) -> Result<Response, ContractError> {
+        self.set_paused(ctx, round_id, true)
+    }
+
+    #[msg(exec)]
+    pub fn unpause(
+        &self,
+        ctx: (DepsMut, Env, MessageInfo),
+        round_id: Option<u64>,
+    ) -> Result<Response, ContractError> {
+        self.set_paused(ctx, round_id, false)
+    }
+
+    /// Pauses or unpauses a single round, or the whole contract when `round_id` is `None`.
+    fn set_paused(
+        &self,
+        ctx: (DepsMut, Env, MessageInfo),
+        round_id: Option<u64>,
+        paused: bool,
+    ) -> Result<Response, ContractError> {
+        let (deps, _, info) = ctx;
+        self.check_owner_permission(&deps, &info)?;
+
+        let mut event = Event::new(if paused { "paused" } else { "unpaused" });
+        match round_id {
+            Some(round_id) => {
+                let mut round = self.rounds.load(deps.storage, &round_id.to_string())?;
+                round.paused = paused;
+                self.rounds
+                    .save(deps.storage, &round_id.to_string(), &round)?;
+                event = event.add_attribute("round_id", round_id.to_string());
+            }
+            None => {
+                self.paused.save(deps.storage, &paused)?;
+                event = event.add_attribute("scope", "global");
+            }
+        }
+
+        let resp = Response::new()
+            .add_attribute("action", if paused { "pause" } else { "unpause" })
+            .add_event(event);
+        Ok(resp)
+    }
+
+    #[msg(exec)]
+    #[allow(clippy::too_many_arguments, clippy::len_zero)]
+    pub fn start_round(
+        &self,
+        ctx: (DepsMut, Env, MessageInfo),
+        tax_adjustment_multiplier: u64,
+        donation_denom: String,
+        voting_unit: Uint128,
+        fund: Uint128,
+        pubkey: Vec<u8>,
+        matching_strategy: Option<MatchingStrategy>,
+    ) -> Result<Response, ContractError> {
+        let (deps, _, info) = ctx;
+        self.check_role(&deps, &info, Role::RoundManager)?;
+
+        let matching_strategy = matching_strategy.unwrap_or_default();
+        match matching_strategy {
+            MatchingStrategy::Capped { cap_bps } if cap_bps == 0 || cap_bps > MAX_CAP_BPS => {
+                return Err(ContractError::InvalidMatchingStrategy);
+            }
+            MatchingStrategy::Pairwise { threshold: 0 } => {
+                return Err(ContractError::InvalidMatchingStrategy);
+            }
+            _ => {}
+        }
+
+        let supply = deps
+            .querier
+            .query_supply(&donation_denom)
+            .unwrap_or_default();
+        if supply.amount.is_zero() {
+            return Err(ContractError::InvalidDenom {
+                denom: donation_denom,
+            });
+        }
+
+        if voting_unit.u128() == 0 {
+            return Err(ContractError::VotingUnitZero {});
+        }
+
+        if pubkey.len() != 65 && pubkey.len() != 0 {
+            return Err(ContractError::InvalidPubkeyLength {});
+        }
+
+        let current_round = self.current_round.load(deps.storage)?;
+        let round_id = current_round + 1;
+
+        let deposited = cw_utils::may_pay(&info, &donation_denom)?.u128();
+        if deposited > fund.u128() {
+            return Err(ContractError::DepositExceedsFund {
+                round_id,
+                remaining: fund.u128(),
+            });
+        }
+        self.current_round.save(deps.storage, &round_id)?;
+
+        let round = Round {
+            id: round_id,
+            tax_adjustment_multiplier,
+            donation_denom,
+            voting_unit,
+            status: RoundStatus::Voting,
+            fund,
+            project_number: 0,
+            total_area: 0,
+            total_amounts: 0,
+            pubkey,
+            voter_count: 0,
+            min_voters: 0,
+            min_contribution: Uint128::zero(),
+            min_donation: Uint128::zero(),
+            max_donation_per_project: Uint128::zero(),
+            max_donation_per_voter: Uint128::zero(),
+            paused: false,
+            withdrawn: 0,
+            fee_bps: 0,
+            fees: 0,
+            fees_claimed: 0,
+            challenge_period: 0,
+            challenge_ends_at: 0,
+            finalized_at: 0,
+            snapshot_hash: String::new(),
+            payout_mode: PayoutMode::Direct,
+            merkle_root: String::new(),
+            merkle_total: 0,
+            allocated: 0,
+            merkle_claimed: 0,
+            claim_deadline: 0,
+            merkle_swept: false,
+            vesting_cliff: 0,
+            vesting_duration: 0,
+            rollover_to: 0,
+            rolled_over: 0,
+            rolled_in: 0,
+            matching_strategy,
+            precision: self
+                .precision
+                .may_load(deps.storage)?
+                .unwrap_or(LEGACY_PRECISION),
+            weight_curve: WeightCurve::Log2,
+            score_type: String::new(),
+            weight_source: WeightSource::Signature,
+            weight_policy: WeightPolicy::Pinned,
+            deposited,
+            matching_paid: 0,
+        };
+
+        self.rounds
+            .save(deps.storage, &round_id.to_string(), &round)?;
+
+        let resp = Response::new()
+            .add_attribute("action", "start_round")
+            .add_event(
+                Event::new("start_round")
+                    .add_attribute("id", round_id.to_string())
+                    .add_attribute("matching_strategy", format!("{:?}", matching_strategy))
+                    .add_attribute("deposited", deposited.to_string()),
+            );
+        Ok(resp)
+    }
+
+    /// Deposits matching for a round, the fund has to be covered before the round can be finalized.
+    #[msg(exec)]
+    pub fn fund_round(
+        &self,
+        ctx: (DepsMut, Env, MessageInfo),
+        round_id: u64,
+    ) -> Result<Response, ContractError> {
+        let (deps, _, info) = ctx;
+
+        let mut round = self.rounds.load(deps.storage, &round_id.to_string())?;
+        if !matches!(round.status, RoundStatus::Voting | RoundStatus::Challenging) {
+            return Err(ContractError::RoundNotInVoting { round_id });
+        }
+        self.check_not_paused(&deps, &round)?;
+
+        let amount = cw_utils::must_pay(&info, &round.donation_denom)?.u128();
+        let remaining = round.fund.u128().saturating_sub(round.deposited);
+        if amount > remaining {
+            return Err(ContractError::DepositExceedsFund {
+                round_id,
+                remaining,
+            });
+        }
+        round.deposited += amount;
+        self.rounds
+            .save(deps.storage, &round_id.to_string(), &round)?;
+
+        let resp = Response::new()
+            .add_attribute("action", "fund_round")
+            .add_event(
+                Event::new("fund_round")
+                    .add_attribute("round_id", round_id.to_string())
+                    .add_attribute("sender", info.sender)
+                    .add_attribute("amounts", amount.to_string())
+                    .add_attribute("deposited", round.deposited.to_string()),
+            );
+        Ok(resp)
+    }
+
+    #[msg(exec)]
+    pub fn batch_upload_project(
+        &self,
+        ctx: (DepsMut, Env, MessageInfo),
+        round_id: u64,
+        owner_addresses: Vec<String>,
+    ) -> Result<Response, ContractError> {
+        let (deps, _, info) = ctx;
+        self.check_role(&deps, &info, Role::ProjectCurator)?;
+
+        let mut round = self.rounds.load(deps.storage, &round_id.to_string())?;
+
+        if round.status != RoundStatus::Voting {
+            return Err(ContractError::RoundNotInVoting { round_id: round.id });
+        }
+        if matches!(round.matching_strategy, MatchingStrategy::Pairwise { .. })
+            && round.project_number + owner_addresses.len() as u64 > MAX_PAIRWISE_PROJECTS
+        {
+            return Err(ContractError::PairwiseProjectsExceeded {
+                round_id,
+                limit: MAX_PAIRWISE_PROJECTS,
+            });
+        }
+
+        owner_addresses.iter().for_each(|addr| {
+            let id = round.project_number + 1;
+            round.project_number = id;
+
+            let project = Project {
+                id,
+                owner: addr.to_string(),
+                area: 0,
+                votes: 0,
+                contribution: 0,
+                status: ProjectStatus::OK,
+                voter_count: 0,
+            };
+            self.projects
+                .save(
+                    deps.storage,
+                    (&round_id.to_string(), &id.to_string()),
+                    &project,
+                )
+                .unwrap();
+        });
+
+        self.rounds
+            .save(deps.storage, &round_id.to_string(), &round)
+            .unwrap();
+
+        let resp = Response::new()
+            .add_attribute("action", "batch_upload_project")
+            .add_event(
+                Event::new("batch_upload_project")
+                    .add_attribute("round_id", round_id.to_string())
+                    .add_attribute("projects", owner_addresses.join(", ").to_string()),
+            );
+        Ok(resp)
+    }
+
+    #[msg(exec)]
+    #[allow(clippy::too_many_arguments, clippy::assign_op_pattern)]
+    pub fn weighted_batch_vote(
+        &self,
+        ctx: (DepsMut, Env, MessageInfo),
+        round_id: u64,
+        project_ids: Vec<u64>,
+        amounts: Vec<Uint128>,
+        score: u64,
+        timestamp: u64,
+        recid: u8,
+        sig: Vec<u8>,
+        sig_chain_id: String,
+        sig_contract_addr: String,
+    ) -> Result<Response, ContractError> {
+        let (mut deps, env, info) = ctx;
+
+        let mut round = self.rounds.load(deps.storage, &round_id.to_string())?;
+
+        if round.status != RoundStatus::Voting {
+            return Err(ContractError::RoundNotInVoting { round_id });
+        }
+        self.check_not_paused(&deps, &round)?;
+
+        if project_ids.len() != amounts.len() {
+            return Err(ContractError::LengthNotMatch {
+                expected: project_ids.len() as u128,
+                actual: amounts.len() as u128,
+            });
+        }
+
+        let scale = 10u128.pow(round.precision);
+        let attested =
+            matches!(round.weight_source, WeightSource::Staking { .. }) || !sig.is_empty();
+        let weight: u128;
+        if let WeightSource::Staking { unit, snapshot } = round.weight_source {
+            // The stake decides the weight, signatures are not needed
+            let staked = self.staked_amount(deps.as_ref(), &round, &info.sender, snapshot)?;
+            let score = u64::try_from(staked.u128() / unit.u128()).unwrap_or(u64::MAX);
+            weight = Self::curve_weight(&round, score)?;
+        } else if sig.is_empty() {
+            // If there is no signature, the weight is 1.0, which means vcDORA is not included in the calculation.
+            weight = scale;
+        } else if sig.len() != 64 {
+            return Err(ContractError::InvalidSignatureLength {});
+        } else {
+            if round.pubkey.is_empty() {
+                return Err(ContractError::PubkeyNotSet {});
+            }
+            // buidl msg
+            let addr = deps.api.addr_canonicalize(info.sender.as_str()).unwrap();
+            let addr_bytes = addr.as_slice();
+            let msg = signature::build_attested_msg(
+                addr_bytes,
+                round_id,
+                &project_ids,
+                &amounts,
+                &round.score_type,
+                score,
+                timestamp,
+                &sig_chain_id,
+                &sig_contract_addr,
+            );
+
+            // verify signature
+            if env.block.time.seconds() > timestamp + 60 * 60 {
+                // 1 hour
+                return Err(ContractError::InvalidSignatureTimestamp {});
+            }
+            let pubkey = signature::recover_pubkey(deps.as_ref(), msg, sig, recid);
+            if pubkey != round.pubkey {
+                return Err(ContractError::InvalidSignature {});
+            }
+
+            // calculate weight, `scale` means 1.0
+            weight = Self::curve_weight(&round, score)?;
+        }
+
+        // Earlier ballots of the voter may hold the weight, depending on the round's policy. Only
+        // attested weights are held, an unsigned ballot must not pin 1.0 for the rest of the round
+        let previous = self
+            .voter_weights
+            .may_load(deps.storage, (&round_id.to_string(), &info.sender))?;
+        let weight = match (round.weight_policy, previous) {
+            (WeightPolicy::Pinned, Some(pinned)) => pinned,
+            (WeightPolicy::Monotonic, Some(pinned)) if weight < pinned => {
+                return Err(ContractError::WeightDecreased { pinned, weight });
+            }
+            _ => weight,
+        };
+        if attested {
+            self.voter_weights.save(
+                deps.storage,
+                (&round_id.to_string(), &info.sender),
+                &weight,
+            )?;
+        }
+
+        // Fees are skimmed from each donation; voter-side limits apply to the gross amount
+        let mut total_amounts = 0;
+        let mut total_fees = 0;
+        let mut total_area = 0;
+
+        let denom = round.donation_denom.clone();
+
+        let decimals = if denom == "inj" {
+            INJ_DECIMALS // hardcode for INJ
+        } else {
+            let denom_metadata = deps.querier.query_denom_metadata(&denom).unwrap();
+
+            let mut denom_unit: Option<DenomUnit> = None;
+            for unit in denom_metadata.denom_units {
+                if unit.denom == denom_metadata.display {
+                    denom_unit = Some(unit);
+                    break;
+                }
+            }
+            denom_unit.unwrap().exponent
+        };
+
+        for (project_id, vote) in project_ids.iter().zip(amounts.iter()) {
+            let amount = vote.u128();
+            total_amounts = total_amounts + amount;
+            let mut project = self.projects.load(
+                deps.storage,
+                (&round_id.to_string(), &project_id.to_string()),
+            )?;
+
+            if amount < round.min_donation.u128() {
+                return Err(ContractError::DonationBelowMinimum {
+                    amount,
+                    min: round.min_donation.u128(),
+                });
+            }
+
+            // A zero cap means the round does not limit donations per project
+            let contribution = self
+                .contributions
+                .may_load(
+                    deps.storage,
+                    (&round_id.to_string(), &project_id.to_string(), &info.sender),
+                )?
+                .unwrap_or_default()
+                + amount;
+            let cap = round.max_donation_per_project.u128();
+            if cap > 0 && contribution > cap {
+                return Err(ContractError::ProjectDonationCapExceeded {
+                    project_id: *project_id,
+                    cap,
+                });
+            }
+            if matches!(round.matching_strategy, MatchingStrategy::Pairwise { .. })
+                && project.voter_count >= MAX_PAIRWISE_VOTERS
+                && !self.votes.has(
+                    deps.storage,
+                    (&round_id.to_string(), &project_id.to_string(), &info.sender),
+                )
+            {
+                return Err(ContractError::PairwiseVotersExceeded {
+                    project_id: *project_id,
+                    limit: MAX_PAIRWISE_VOTERS,
+                });
+            }
+            self.contributions.save(
+                deps.storage,
+                (&round_id.to_string(), &project_id.to_string(), &info.sender),
+                &contribution,
+            )?;
+
+            let pow_10_decimals = 10u128.pow(decimals);
+            let votes = amount * round.voting_unit.u128() / pow_10_decimals;
+            if votes == 0 {
+                return Err(ContractError::TooSmallAmount { amount });
+            }
+
+            project.votes = project.votes + votes;
+            let fee = Uint128::from(amount)
+                .multiply_ratio(round.fee_bps, MAX_FEE_BPS)
+                .u128();
+            total_fees += fee;
+            project.contribution = project.contribution + amount - fee;
+            let net_contribution = self
+                .net_contributions
+                .may_load(
+                    deps.storage,
+                    (&round_id.to_string(), &project_id.to_string(), &info.sender),
+                )?
+                .unwrap_or_default()
+                + amount
+                - fee;
+            self.net_contributions.save(
+                deps.storage,
+                (&round_id.to_string(), &project_id.to_string(), &info.sender),
+                &net_contribution,
+            )?;
+
+            // Compute area difference and update project/round area
+            let mut old_votes: u128 = 0;
+            let mut new_votes: u128 = votes;
+
+            let votes = self.votes.may_load(
+                deps.storage,
+                (&round_id.to_string(), &project_id.to_string(), &info.sender),
+            )?;
+            match votes {
+                Some(votes) => {
+                    old_votes = votes;
+                    new_votes = new_votes + old_votes;
+                }
+                None => {
+                    // First time this address supports the project
+                    project.voter_count += 1;
+                    self.voted_projects.save(
+                        deps.storage,
+                        (&round_id.to_string(), &info.sender, &project_id.to_string()),
+                        &Empty {},
+                    )?;
+                }
+            }
+            deps.api.debug(&format!(
+                "old_votes: {} new_votes: {}",
+                old_votes, new_votes
+            ));
+            self.votes.save(
+                deps.storage,
+                (&round_id.to_string(), &project_id.to_string(), &info.sender),
+                &new_votes,
+            )?;
+            if let MatchingStrategy::Pairwise { threshold } = round.matching_strategy {
+                self.update_pairwise_areas(
+                    &mut deps,
+                    &round_id.to_string(),
+                    &project_id.to_string(),
+                    &info.sender,
+                    old_votes,
+                    new_votes,
+                    threshold,
+                    round.precision,
+                )?;
+            }
+            if round.matching_strategy == MatchingStrategy::Cluster {
+                let cluster = self
+                    .clusters
+                    .may_load(deps.storage, (&round_id.to_string(), &info.sender))?;
+                self.update_cluster_area(
+                    &mut deps,
+                    &round_id.to_string(),
+                    &project_id.to_string(),
+                    &Self::cluster_key(cluster, &info.sender),
+                    old_votes,
+                    new_votes,
+                    round.precision,
+                )?;
+            }
+
+            let area_diff = match round.matching_strategy {
+                // Pairwise and cluster areas are kept apart, the project keeps the classic area for
+                // stats and leaderboards
+                MatchingStrategy::Classic
+                | MatchingStrategy::Capped { .. }
+                | MatchingStrategy::Pairwise { .. }
+                | MatchingStrategy::Cluster => {
+                    let old_area = fixed::sqrt(old_votes, round.precision); // area is scaled by `scale` to avoid float
+                    let new_area = fixed::sqrt(new_votes, round.precision);
+                    deps.api
+                        .debug(&format!("old_area: {} new_area: {}", old_area, new_area));
+
+                    new_area * weight / scale - old_area // adjust by weight, div `scale` to get the real weight
+                }
+                MatchingStrategy::Linear => (new_votes - old_votes) * weight / scale,
+            };
+
+            project.area = project.area + area_diff;
+            total_area = total_area + area_diff;
+            let voter_area = self
+                .voter_areas
+                .may_load(
+                    deps.storage,
+                    (&round_id.to_string(), &project_id.to_string(), &info.sender),
+                )?
+                .unwrap_or_default()
+                + area_diff;
+            self.voter_areas.save(
+                deps.storage,
+                (&round_id.to_string(), &project_id.to_string(), &info.sender),
+                &voter_area,
+            )?;
+            deps.api
+                .debug(&format!("total_area inner: {} {}", total_area, area_diff));
+
+            self.projects.save(
+                deps.storage,
+                (&round_id.to_string(), &project_id.to_string()),
+                &project,
+            )?;
+        }
+        let denom = round.donation_denom.clone();
+        let transfer = cw_utils::must_pay(&info, &denom)
+            .map_err(|err| StdError::generic_err(err.to_string()))?
+            .u128();
+        if transfer != total_amounts {
+            return Err(ContractError::InvalidAmount {
+                expected: total_amounts,
+                actual: transfer,
+            });
+        }
+
+        // Count the voter once per round, the first time they appear
+        let voter_total = self
+            .voters
+            .may_load(deps.storage, (&round_id.to_string(), &info.sender))?;
+        if voter_total.is_none() {
+            round.voter_count += 1;
+        }
+        let voter_total = voter_total.unwrap_or_default() + total_amounts;
+        let cap = round.max_donation_per_voter.u128();
+        if cap > 0 && voter_total > cap {
+            return Err(ContractError::VoterDonationCapExceeded { cap });
+        }
+        self.voters.save(
+            deps.storage,
+            (&round_id.to_string(), &info.sender),
+            &voter_total,
+        )?;
+
+        round.total_area += total_area;
+        round.total_amounts += total_amounts - total_fees;
+        round.fees += total_fees;
+        self.rounds
+            .save(deps.storage, &round_id.to_string(), &round)?;
+
+        let resp = Response::new()
+            .add_attribute("action", "weighted_batch_vote")
+            .add_event(
+                Event::new("weighted_batch_vote")
+                    .add_attribute("voter", info.sender)
+                    .add_attribute("round_id", round_id.to_string())
+                    .add_attribute("score", score.to_string())
+                    .add_attribute("score_type", round.score_type.clone())
+                    .add_attribute("weight", weight.to_string())
+                    .add_attribute(
+                        "projects",
+                        format!(
+                            "{:?}",
+                            project_ids
+                                .iter()
+                                .map(|x| x.to_string())
+                                .collect::<Vec<String>>()
+                                .join(",")
+                        ),
+                    )
+                    .add_attribute(
+                        "amounts",
+                        format!(
+                            "{:?}",
+                            amounts
+                                .iter()
+                                .map(|x| x.to_string())
+                                .collect::<Vec<String>>()
+                                .join(",")
+                        ),
+                    )
+                    .add_attribute("total_area", total_area.to_string())
+                    .add_attribute("fees", total_fees.to_string()),
+            );
+        Ok(resp)
+    }
+
+    /// Takes donations back from projects while the round is voting. The votes and area they
+    /// earned are removed and the donation is refunded net of the fees taken when it was cast.
+    /// Support moves to other projects by retracting and voting again.
+    #[msg(exec)]
+    pub fn retract_votes(
+        &self,
+        ctx: (DepsMut, Env, MessageInfo),
+        round_id: u64,
+        project_ids: Vec<u64>,
+        amounts: Vec<Uint128>,
+    ) -> Result<Response, ContractError> {
+        let (mut deps, _, info) = ctx;
+
+        let mut round = self.rounds.load(deps.storage, &round_id.to_string())?;
+
+        if round.status != RoundStatus::Voting {
+            return Err(ContractError::RoundNotInVoting { round_id });
+        }
+        self.check_not_paused(&deps, &round)?;
+
+        if project_ids.len() != amounts.len() {
+            return Err(ContractError::LengthNotMatch {
+                expected: project_ids.len() as u128,
+                actual: amounts.len() as u128,
+            });
+        }
+
+        let mut total_amounts = 0;
+        let mut total_refund = 0;
+        let mut total_area = 0;
+
+        for (project_id, amount) in project_ids.iter().zip(amounts.iter()) {
+            let amount = amount.u128();
+            let key = (round_id.to_string(), project_id.to_string());
+            let mut project = self.projects.load(deps.storage, (&key.0, &key.1))?;
+
+            let contribution = self
+                .contributions
+                .may_load(deps.storage, (&key.0, &key.1, &info.sender))?
+                .unwrap_or_default();
+            if amount == 0 {
+                return Err(ContractError::TooSmallAmount { amount });
+            }
+            if amount > contribution {
+                return Err(ContractError::RetractExceedsContribution {
+                    project_id: *project_id,
+                    contribution,
+                });
+            }
+
+            // Donations made before retractions were tracked can't be taken back exactly
+            let (Some(net_contribution), Some(old_area)) = (
+                self.net_contributions
+                    .may_load(deps.storage, (&key.0, &key.1, &info.sender))?,
+                self.voter_areas
+                    .may_load(deps.storage, (&key.0, &key.1, &info.sender))?,
+            ) else {
+                return Err(ContractError::RetractNotTracked {
+                    project_id: *project_id,
+                });
+            };
+
+            // Votes, area and the net donation leave in proportion to the retracted amount, all
+            // of them once nothing is left
+            let old_votes = self
+                .votes
+                .load(deps.storage, (&key.0, &key.1, &info.sender))?;
+            let new_votes = old_votes
+                - Uint128::from(old_votes)
+                    .multiply_ratio(amount, contribution)
+                    .u128();
+            let new_area = match round.matching_strategy {
+                MatchingStrategy::Linear => {
+                    Uint128::from(old_area).multiply_ratio(new_votes, old_votes)
+                }
+                _ => Uint128::from(old_area).multiply_ratio(
+                    fixed::sqrt(new_votes, round.precision),
+                    fixed::sqrt(old_votes, round.precision),
+                ),
+            }
+            .u128();
+            let refund = Uint128::from(net_contribution)
+                .multiply_ratio(amount, contribution)
+                .u128();
+            deps.api.debug(&format!(
+                "old_votes: {} new_votes: {} old_area: {} new_area: {}",
+                old_votes, new_votes, old_area, new_area
+            ));
+            project.votes = Self::checked_sub(project.votes, old_votes - new_votes)?;
+            project.contribution = Self::checked_sub(project.contribution, refund)?;
+            project.area = Self::checked_sub(project.area, old_area - new_area)?;
+
+            if contribution == amount {
+                self.contributions
+                    .remove(deps.storage, (&key.0, &key.1, &info.sender));
+                self.net_contributions
+                    .remove(deps.storage, (&key.0, &key.1, &info.sender));
+                self.votes
+                    .remove(deps.storage, (&key.0, &key.1, &info.sender));
+                self.voted_projects
+                    .remove(deps.storage, (&key.0, &info.sender, &key.1));
+                self.voter_areas
+                    .remove(deps.storage, (&key.0, &key.1, &info.sender));
+                project.voter_count -= 1;
+            } else {
+                self.contributions.save(
+                    deps.storage,
+                    (&key.0, &key.1, &info.sender),
+                    &(contribution - amount),
+                )?;
+                self.net_contributions.save(
+                    deps.storage,
+                    (&key.0, &key.1, &info.sender),
+                    &(net_contribution - refund),
+                )?;
+                self.votes
+                    .save(deps.storage, (&key.0, &key.1, &info.sender), &new_votes)?;
+                self.voter_areas
+                    .save(deps.storage, (&key.0, &key.1, &info.sender), &new_area)?;
+            }
+            if let MatchingStrategy::Pairwise { threshold } = round.matching_strategy {
+                self.update_pairwise_areas(
+                    &mut deps,
+                    &key.0,
+                    &key.1,
+                    &info.sender,
+                    old_votes,
+                    new_votes,
+                    threshold,
+                    round.precision,
+                )?;
+            }
+            if round.matching_strategy == MatchingStrategy::Cluster {
+                let cluster = self
+                    .clusters
+                    .may_load(deps.storage, (&key.0, &info.sender))?;
+                self.update_cluster_area(
+                    &mut deps,
+                    &key.0,
+                    &key.1,
+                    &Self::cluster_key(cluster, &info.sender),
+                    old_votes,
+                    new_votes,
+                    round.precision,
+                )?;
+            }
+
+            total_amounts += amount;
+            total_refund += refund;
+            total_area += old_area - new_area;
+
+            self.projects
+                .save(deps.storage, (&key.0, &key.1), &project)?;
+        }
+
+        // The voter stops counting once they have no donation left in the round
+        let voter_total = Self::checked_sub(
+            self.voters
+                .load(deps.storage, (&round_id.to_string(), &info.sender))?,
+            total_amounts,
+        )?;
+        if voter_total == 0 {
+            self.voters
+                .remove(deps.storage, (&round_id.to_string(), &info.sender));
+            round.voter_count -= 1;
+        } else {
+            self.voters.save(
+                deps.storage,
+                (&round_id.to_string(), &info.sender),
+                &voter_total,
+            )?;
+        }
+
+        round.total_area = Self::checked_sub(round.total_area, total_area)?;
+        round.total_amounts = Self::checked_sub(round.total_amounts, total_refund)?;
+        self.rounds
+            .save(deps.storage, &round_id.to_string(), &round)?;
+
+        let resp = if total_refund > 0 {
+            let message = BankMsg::Send {
+                to_address: info.sender.to_string(),
+                amount: coins(total_refund, &round.donation_denom),
+            };
+
+            Response::new().add_message(message)
+        } else {
+            Response::new()
+        };
+        let resp = resp.add_attribute("action", "retract_votes").add_event(
+            Event::new("retract_votes")
+                .add_attribute("voter", info.sender)
+                .add_attribute("round_id", round_id.to_string())
+                .add_attribute(
+                    "projects",
+                    project_ids
+                        .iter()
+                        .map(|x| x.to_string())
+                        .collect::<Vec<String>>()
+                        .join(","),
+                )
+                .add_attribute("total_area", total_area.to_string())
+                .add_attribute("refund", total_refund.to_string()),
+        );
+        Ok(resp)
+    }
+
+    #[msg(exec)]
+    pub fn set_eligibility(
+        &self,
+        ctx: (DepsMut, Env, MessageInfo),
+        round_id: u64,
+        min_voters: u64,
+        min_contribution: Uint128,
+    ) -> Result<Response, ContractError> {
+        let (deps, _, info) = ctx;
+        self.check_role(&deps, &info, Role::RoundManager)?;
+
+        let mut round = self.rounds.load(deps.storage, &round_id.to_string())?;
+
+        if round.status != RoundStatus::Voting {
+            return Err(ContractError::RoundNotInVoting { round_id });
+        }
+
+        round.min_voters = min_voters;
+        round.min_contribution = min_contribution;
+        self.rounds
+            .save(deps.storage, &round_id.to_string(), &round)?;
+
+        let resp = Response::new()
+            .add_attribute("action", "set_eligibility")
+            .add_event(
+                Event::new("set_eligibility")
+                    .add_attribute("round_id", round_id.to_string())
+                    .add_attribute("min_voters", min_voters.to_string())
+                    .add_attribute("min_contribution", min_contribution.to_string()),
+            );
+        Ok(resp)
+    }
+
+    #[msg(exec)]
+    pub fn set_donation_limits(
+        &self,
+        ctx: (DepsMut, Env, MessageInfo),
+        round_id: u64,
+        min_donation: Uint128,
+        max_donation_per_project: Uint128,
+        max_donation_per_voter: Uint128,
+    ) -> Result<Response, ContractError> {
+        let (deps, _, info) = ctx;
+        self.check_role(&deps, &info, Role::RoundManager)?;
+
+        let mut round = self.rounds.load(deps.storage, &round_id.to_string())?;
+
+        if round.status != RoundStatus::Voting {
+            return Err(ContractError::RoundNotInVoting { round_id });
+        }
+
+        round.min_donation = min_donation;
+        round.max_donation_per_project = max_donation_per_project;
+        round.max_donation_per_voter = max_donation_per_voter;
+        self.rounds
+            .save(deps.storage, &round_id.to_string(), &round)?;
+
+        let resp = Response::new()
+            .add_attribute("action", "set_donation_limits")
+            .add_event(
+                Event::new("set_donation_limits")
+                    .add_attribute("round_id", round_id.to_string())
+                    .add_attribute("min_donation", min_donation.to_string())
+                    .add_attribute(
+                        "max_donation_per_project",
+                        max_donation_per_project.to_string(),
+                    )
+                    .add_attribute("max_donation_per_voter", max_donation_per_voter.to_string()),
+            );
+        Ok(resp)
+    }
+
+    #[msg(exec)]
+    pub fn set_fee(
+        &self,
+        ctx: (DepsMut, Env, MessageInfo),
+        round_id: u64,
+        fee_bps: u64,
+    ) -> Result<Response, ContractError> {
+        let (deps, _, info) = ctx;
+        self.check_role(&deps, &info, Role::RoundManager)?;
+
+        if fee_bps > MAX_FEE_BPS {
+            return Err(ContractError::InvalidFeeBps { fee_bps });
+        }
+
+        let mut round = self.rounds.load(deps.storage, &round_id.to_string())?;
+
+        if round.status != RoundStatus::Voting {
+            return Err(ContractError::RoundNotInVoting { round_id });
+        }
+
+        round.fee_bps = fee_bps;
+        self.rounds
+            .save(deps.storage, &round_id.to_string(), &round)?;
+
+        let resp = Response::new()
+            .add_attribute("action", "set_fee")
+            .add_event(
+                Event::new("set_fee")
+                    .add_attribute("round_id", round_id.to_string())
+                    .add_attribute("fee_bps", fee_bps.to_string()),
+            );
+        Ok(resp)
+    }
+
+    #[msg(exec)]
+    pub fn end_round(
+        &self,
+        ctx: (DepsMut, Env, MessageInfo),
+        round_id: u64,
+    ) -> Result<Response, ContractError> {
+        let (deps, env, info) = ctx;
+        self.check_role(&deps, &info, Role::RoundManager)?;
+        self.check_no_approval_required(&deps)?;
+
+        self.apply_end_round(deps, env, round_id)
+    }
+
+    /// Ends voting and publishes the distribution. With a challenge period the round
+    /// stays in `Challenging` until `lock_distribution` is called after the window.
+    fn apply_end_round(
+        &self,
+        mut deps: DepsMut,
+        env: Env,
+        round_id: u64,
+    ) -> Result<Response, ContractError> {
+        let mut round = self.rounds.load(deps.storage, &round_id.to_string())?;
+
+        if round.status != RoundStatus::Voting {
+            return Err(ContractError::RoundNotInVoting { round_id });
+        }
+
+        let mut resp = Response::new().add_attribute("action", "end_round");
+        if round.challenge_period > 0 {
+            round.status = RoundStatus::Challenging;
+            round.challenge_ends_at = env.block.time.seconds() + round.challenge_period;
+            self.rounds
+                .save(deps.storage, &round_id.to_string(), &round)?;
+            self.publish_distribution(&mut deps, &round)?;
+        } else {
+            resp = resp.add_event(self.finalize_round(&mut deps, &env, &mut round)?);
+        }
+
+        Ok(resp.add_event(
+            Event::new("end_round")
+                .add_attribute("round_id", round_id.to_string())
+                .add_attribute("challenge_ends_at", round.challenge_ends_at.to_string()),
+        ))
+    }
+
+    #[msg(exec)]
+    pub fn set_challenge_period(
+        &self,
+        ctx: (DepsMut, Env, MessageInfo),
+        round_id: u64,
+        challenge_period: u64,
+    ) -> Result<Response, ContractError> {
+        let (deps, _, info) = ctx;
+        self.check_role(&deps, &info, Role::RoundManager)?;
+
+        let mut round = self.rounds.load(deps.storage, &round_id.to_string())?;
+
+        if round.status != RoundStatus::Voting {
+            return Err(ContractError::RoundNotInVoting { round_id });
+        }
+
+        round.challenge_period = challenge_period;
+        self.rounds
+            .save(deps.storage, &round_id.to_string(), &round)?;
+
+        let resp = Response::new()
+            .add_attribute("action", "set_challenge_period")
+            .add_event(
+                Event::new("set_challenge_period")
+                    .add_attribute("round_id", round_id.to_string())
+                    .add_attribute("challenge_period", challenge_period.to_string()),
+            );
+        Ok(resp)
+    }
+
+    #[msg(exec)]
+    pub fn ban_project(
+        &self,
+        ctx: (DepsMut, Env, MessageInfo),
+        round_id: u64,
+        project_id: u64,
+        banned: bool,
+    ) -> Result<Response, ContractError> {
+        let (mut deps, _, info) = ctx;
+        self.check_role(&deps, &info, Role::ProjectCurator)?;
+
+        let round = self.load_adjustable_round(&deps, round_id)?;
+        let mut project = self
+            .projects
+            .may_load(
+                deps.storage,
+                (&round_id.to_string(), &project_id.to_string()),
+            )?
+            .ok_or(ContractError::ProjectNotExist { project_id })?;
+
+        project.status = if banned {
+            ProjectStatus::Banned
+        } else {
+            ProjectStatus::OK
+        };
+        self.projects.save(
+            deps.storage,
+            (&round_id.to_string(), &project_id.to_string()),
+            &project,
+        )?;
+        self.publish_distribution(&mut deps, &round)?;
+
+        let resp = Response::new()
+            .add_attribute("action", "ban_project")
+            .add_event(
+                Event::new("ban_project")
+                    .add_attribute("round_id", round_id.to_string())
+                    .add_attribute("project_id", project_id.to_string())
+                    .add_attribute("banned", banned.to_string()),
+            );
+        Ok(resp)
+    }
+
+    /// Sybil adjustment: overrides a project's area after off-chain review. Pairwise and cluster
+    /// rounds derive their areas from votes, so they are adjusted through bans and clusters.
+    #[msg(exec)]
+    pub fn adjust_project_area(
+        &self,
+        ctx: (DepsMut, Env, MessageInfo),
+        round_id: u64,
+        project_id: u64,
+        area: u128,
+    ) -> Result<Response, ContractError> {
+        let (mut deps, _, info) = ctx;
+        self.check_role(&deps, &info, Role::ProjectCurator)?;
+
+        let mut round = self.load_adjustable_round(&deps, round_id)?;
+        if matches!(
+            round.matching_strategy,
+            MatchingStrategy::Pairwise { .. } | MatchingStrategy::Cluster
+        ) {
+            return Err(ContractError::WrongMatchingStrategy { round_id });
+        }
+        let mut project = self
+            .projects
+            .may_load(
+                deps.storage,
+                (&round_id.to_string(), &project_id.to_string()),
+            )?
+            .ok_or(ContractError::ProjectNotExist { project_id })?;
+
+        round.total_area = round.total_area - project.area + area;
+        let old_area = project.area;
+        project.area = area;
+        self.projects.save(
+            deps.storage,
+            (&round_id.to_string(), &project_id.to_string()),
+            &project,
+        )?;
+        self.rounds
+            .save(deps.storage, &round_id.to_string(), &round)?;
+        self.publish_distribution(&mut deps, &round)?;
+
+        let resp = Response::new()
+            .add_attribute("action", "adjust_project_area")
+            .add_event(
+                Event::new("adjust_project_area")
+                    .add_attribute("round_id", round_id.to_string())
+                    .add_attribute("project_id", project_id.to_string())
+                    .add_attribute("old_area", old_area.to_string())
+                    .add_attribute("area", area.to_string()),
+            );
+        Ok(resp)
+    }
+
+    /// Assigns voters to clusters used by the cluster matching strategy, e.g. from sybil analysis.
+    #[msg(exec)]
+    pub fn upload_voter_clusters(
+        &self,
+        ctx: (DepsMut, Env, MessageInfo),
+        round_id: u64,
+        voters: Vec<String>,
+        clusters: Vec<u64>,
+    ) -> Result<Response, ContractError> {
+        let (mut deps, _, info) = ctx;
+        self.check_role(&deps, &info, Role::ProjectCurator)?;
+
+        let round = self.rounds.load(deps.storage, &round_id.to_string())?;
+        if !matches!(round.status, RoundStatus::Voting | RoundStatus::Challenging) {
+            return Err(ContractError::SnapshotFrozen { round_id });
+        }
+        if voters.len() != clusters.len() {
+            return Err(ContractError::LengthNotMatch {
+                expected: voters.len() as u128,
+                actual: clusters.len() as u128,
+            });
+        }
+
+        let key = round_id.to_string();
+        for (voter, cluster) in voters.iter().zip(clusters.iter()) {
+            let voter = deps.api.addr_validate(voter)?;
+            let old_cluster = self.clusters.may_load(deps.storage, (&key, &voter))?;
+            if round.matching_strategy == MatchingStrategy::Cluster && old_cluster != Some(*cluster)
+            {
+                // Move the voter's votes over to the new cluster on every project they fund
+                let old_key = Self::cluster_key(old_cluster, &voter);
+                let new_key = Self::cluster_key(Some(*cluster), &voter);
+                let project_ids = self
+                    .voted_projects
+                    .prefix((&key, &voter))
+                    .keys(deps.storage, None, None, Order::Ascending)
+                    .collect::<StdResult<Vec<String>>>()?;
+                for project_id in project_ids {
+                    let votes = self.votes.load(deps.storage, (&key, &project_id, &voter))?;
+                    self.update_cluster_area(
+                        &mut deps,
+                        &key,
+                        &project_id,
+                        &old_key,
+                        votes,
+                        0,
+                        round.precision,
+                    )?;
+                    self.update_cluster_area(
+                        &mut deps,
+                        &key,
+                        &project_id,
+                        &new_key,
+                        0,
+                        votes,
+                        round.precision,
+                    )?;
+                }
+            }
+            self.clusters.save(deps.storage, (&key, &voter), cluster)?;
+        }
+        if round.status == RoundStatus::Challenging {
+            self.publish_distribution(&mut deps, &round)?;
+        }
+
+        let resp = Response::new()
+            .add_attribute("action", "upload_voter_clusters")
+            .add_event(
+                Event::new("upload_voter_clusters")
+                    .add_attribute("round_id", round_id.to_string())
+                    .add_attribute("voters", voters.len().to_string()),
+            );
+        Ok(resp)
+    }
+
+    /// Bans and sybil adjustments are only allowed between the end of voting and the lock of
+    /// the distribution, so they can't be raced by votes.
+    fn load_adjustable_round(&self, deps: &DepsMut, round_id: u64) -> Result<Round, ContractError> {
+        let round = self.rounds.load(deps.storage, &round_id.to_string())?;
+        match round.status {
+            RoundStatus::Challenging => Ok(round),
+            _ => Err(ContractError::RoundNotChallenging { round_id }),
+        }
+    }
+
+    #[msg(exec)]
+    pub fn lock_distribution(
+        &self,
+        ctx: (DepsMut, Env, MessageInfo),
+        round_id: u64,
+    ) -> Result<Response, ContractError> {
+        let (mut deps, env, _) = ctx;
+
+        let mut round = self.rounds.load(deps.storage, &round_id.to_string())?;
+
+        if round.status != RoundStatus::Challenging {
+            return Err(ContractError::RoundNotChallenging { round_id });
+        }
+        if env.block.time.seconds() < round.challenge_ends_at {
+            return Err(ContractError::ChallengePeriodNotOver {
+                round_id,
+                ends_at: round.challenge_ends_at,
+            });
+        }
+
+        let event = self.finalize_round(&mut deps, &env, &mut round)?;
+
+        let resp = Response::new()
+            .add_attribute("action", "lock_distribution")
+            .add_event(
+                Event::new("lock_distribution").add_attribute("round_id", round_id.to_string()),
+            )
+            .add_event(event);
+        Ok(resp)
+    }
+
+    /// Pays the project owner the matching allocated in the locked distribution.
+    #[msg(exec)]
+    pub fn claim(
+        &self,
+        ctx: (DepsMut, Env, MessageInfo),
+        round_id: u64,
+        project_id: u64,
+    ) -> Result<Response, ContractError> {
+        let (deps, env, info) = ctx;
+
+        let mut round = self.rounds.load(deps.storage, &round_id.to_string())?;
+        if !matches!(round.status, RoundStatus::Finished | RoundStatus::Withdrawn) {
+            return Err(ContractError::RoundNotEnded { round_id });
+        }
+        if round.payout_mode != PayoutMode::Direct {
+            return Err(ContractError::WrongPayoutMode { round_id });
+        }
+        if round.rollover_to != 0 {
+            return Err(ContractError::RoundRolledOver { round_id });
+        }
+        if round.claim_deadline > 0 && env.block.time.seconds() >= round.claim_deadline {
+            return Err(ContractError::ClaimDeadline {
+                round_id,
+                deadline: round.claim_deadline,
+            });
+        }
+        self.check_not_paused(&deps, &round)?;
+
+        let grant = self
+            .grants
+            .may_load(
+                deps.storage,
+                (&round_id.to_string(), &project_id.to_string()),
+            )?
+            .ok_or(ContractError::ProjectNotExist { project_id })?;
+        if info.sender != grant.owner {
+            return Err(ContractError::Unauthorized {
+                sender: info.sender,
+            });
+        }
+
+        let claimed = self
+            .claims
+            .may_load(
+                deps.storage,
+                (&round_id.to_string(), &project_id.to_string()),
+            )?
+            .unwrap_or_default();
+        let amounts = grant.matching - claimed;
+        if amounts == 0 {
+            return Err(ContractError::NothingToClaim { project_id });
+        }
+        Self::release_matching(&mut round, amounts)?;
+        self.rounds
+            .save(deps.storage, &round_id.to_string(), &round)?;
+        self.claims.save(
+            deps.storage,
+            (&round_id.to_string(), &project_id.to_string()),
+            &grant.matching,
+        )?;
+
+        let message = BankMsg::Send {
+            to_address: grant.owner.clone(),
+            amount: coins(amounts, &round.donation_denom),
+        };
+        let resp = Response::new()
+            .add_message(message)
+            .add_attribute("action", "claim")
+            .add_event(
+                Event::new("claim")
+                    .add_attribute("round_id", round_id.to_string())
+                    .add_attribute("project_id", project_id.to_string())
+                    .add_attribute("owner", grant.owner)
+                    .add_attribute("amounts", amounts.to_string()),
+            );
+        Ok(resp)
+    }
+
+    /// Closes direct claims of a round at `claim_deadline`, grants not claimed by then can be
+    /// rolled over.
+    #[msg(exec)]
+    pub fn set_claim_deadline(
+        &self,
+        ctx: (DepsMut, Env, MessageInfo),
+        round_id: u64,
+        claim_deadline: u64,
+    ) -> Result<Response, ContractError> {
+        let (deps, env, info) = ctx;
+        self.check_role(&deps, &info, Role::RoundManager)?;
+
+        let mut round = self.rounds.load(deps.storage, &round_id.to_string())?;
+        if !matches!(round.status, RoundStatus::Finished | RoundStatus::Withdrawn) {
+            return Err(ContractError::RoundNotEnded { round_id });
+        }
+        if round.payout_mode != PayoutMode::Direct {
+            return Err(ContractError::WrongPayoutMode { round_id });
+        }
+        if round.rollover_to != 0 {
+            return Err(ContractError::RoundRolledOver { round_id });
+        }
+        if claim_deadline <= env.block.time.seconds() {
+            return Err(ContractError::ClaimDeadline {
+                round_id,
+                deadline: claim_deadline,
+            });
+        }
+
+        round.claim_deadline = claim_deadline;
+        self.rounds
+            .save(deps.storage, &round_id.to_string(), &round)?;
+
+        let resp = Response::new()
+            .add_attribute("action", "set_claim_deadline")
+            .add_event(
+                Event::new("set_claim_deadline")
+                    .add_attribute("round_id", round_id.to_string())
+                    .add_attribute("claim_deadline", claim_deadline.to_string()),
+            );
+        Ok(resp)
+    }
+
+    #[msg(exec)]
+    pub fn set_pubkey(
+        &self,
+        ctx: (DepsMut, Env, MessageInfo),
+        round_id: u64,
+        pubkey: Vec<u8>,
+    ) -> Result<Response, ContractError> {
+        let (mut deps, env, info) = ctx;
+        self.check_role(&deps, &info, Role::SignerManager)?;
+
+        if pubkey.len() != 65 {
+            return Err(ContractError::InvalidPubkeyLength {});
+        }
+
+        let action = AdminAction::SetPubkey { round_id, pubkey };
+        match self.queue_action(&mut deps, &env, &info, action.clone())? {
+            Some(resp) => Ok(resp),
+            None => self.run_action(deps, env, action),
+        }
+    }
+
+    #[allow(clippy::needless_borrows_for_generic_args)]
+    fn apply_set_pubkey(
+        &self,
+        deps: DepsMut,
+        round_id: u64,
+        pubkey: Vec<u8>,
+    ) -> Result<Response, ContractError> {
+        if pubkey.len() != 65 {
+            return Err(ContractError::InvalidPubkeyLength {});
+        }
+
+        let mut round = self.rounds.load(deps.storage, &round_id.to_string())?;
+
+        if round.status != RoundStatus::Voting {
+            return Err(ContractError::RoundNotInVoting { round_id });
+        }
+
+        round.pubkey = pubkey.clone();
+        self.rounds
+            .save(deps.storage, &round_id.to_string(), &round)?;
+
+        let resp = Response::new()
+            .add_attribute("action", "set_pubkey")
+            .add_event(
+                Event::new("set_pubkey").add_attribute("pubkey", hex::encode(&pubkey.as_slice())),
+            );
+        Ok(resp)
+    }
+
+    #[msg(exec)]
+    pub fn withdraw(
+        &self,
+        ctx: (DepsMut, Env, MessageInfo),
+        round_id: u64,
+        amount: Option<Uint128>,
+    ) -> Result<Response, ContractError> {
+        let (mut deps, env, info) = ctx;
+        self.check_role(&deps, &info, Role::Treasurer)?;
+        self.check_no_approval_required(&deps)?;
+
+        let action = AdminAction::Withdraw { round_id, amount };
+        match self.queue_action(&mut deps, &env, &info, action.clone())? {
+            Some(resp) => Ok(resp),
+            None => self.run_action(deps, env, action),
+        }
+    }
+
+    fn apply_withdraw(
+        &self,
+        deps: DepsMut,
+        env: Env,
+        round_id: u64,
+        amount: Option<Uint128>,
+    ) -> Result<Response, ContractError> {
+        let mut round = self.rounds.load(deps.storage, &round_id.to_string())?;
+        let denom = round.donation_denom.clone();
+
+        if round.status != RoundStatus::Finished {
+            return Err(ContractError::RoundNotEnded { round_id });
+        }
+        self.check_not_paused(&deps, &round)?;
+
+        // Donations always go to the treasury, never to the calling admin
+        let available = round.total_amounts - round.withdrawn;
+        let amounts = amount.map(|amount| amount.u128()).unwrap_or(available);
+        if amounts > available {
+            return Err(ContractError::WithdrawExceedsAvailable {
+                available,
+                requested: amounts,
+            });
+        }
+        let treasury = self.treasury((deps.as_ref(), env))?.treasury;
+
+        round.withdrawn += amounts;
+        if round.withdrawn == round.total_amounts {
+            round.status = RoundStatus::Withdrawn;
+        }
+        self.rounds
+            .save(deps.storage, &round_id.to_string(), &round)?;
+
+        let resp = if amounts > 0 {
+            let message = BankMsg::Send {
+                to_address: treasury.clone(),
+                amount: coins(amounts, &denom),
+            };
+
+            Response::new().add_message(message)
+        } else {
+            Response::new()
+        };
+
+        let resp = resp.add_attribute("action", "withdraw").add_event(
+            Event::new("withdraw")
+                .add_attribute("round_id", round_id.to_string())
+                .add_attribute("treasury", treasury)
+                .add_attribute("amounts", amounts.to_string())
+                .add_attribute("withdrawn", round.withdrawn.to_string()),
+        );
+        Ok(resp)
+    }
+
+    #[msg(exec)]
+    pub fn claim_fees(
+        &self,
+        ctx: (DepsMut, Env, MessageInfo),
+        round_id: u64,
+    ) -> Result<Response, ContractError> {
+        let (deps, env, info) = ctx;
+
+        let fee_collector = self.fee_collector((deps.as_ref(), env))?.fee_collector;
+        if info.sender != fee_collector {
+            return Err(ContractError::Unauthorized {
+                sender: info.sender,
+            });
+        }
+
+        let mut round = self.rounds.load(deps.storage, &round_id.to_string())?;
+        self.check_not_paused(&deps, &round)?;
+
+        let amounts = round.fees - round.fees_claimed;
+        round.fees_claimed = round.fees;
+        self.rounds
+            .save(deps.storage, &round_id.to_string(), &round)?;
+
+        let resp = if amounts > 0 {
+            let message = BankMsg::Send {
+                to_address: fee_collector.clone(),
+                amount: coins(amounts, &round.donation_denom),
+            };
+
+            Response::new().add_message(message)
+        } else {
+            Response::new()
+        };
+
+        let resp = resp.add_attribute("action", "claim_fees").add_event(
+            Event::new("claim_fees")
+                .add_attribute("round_id", round_id.to_string())
+                .add_attribute("fee_collector", fee_collector)
+                .add_attribute("amounts", amounts.to_string()),
+        );
+        Ok(resp)
+    }
+
+    /// Chooses how attested scores amplify votes, only before the first vote of the round.
+    #[msg(exec)]
+    pub fn set_weight_curve(
+        &self,
+        ctx: (DepsMut, Env, MessageInfo),
+        round_id: u64,
+        weight_curve: WeightCurve,
+    ) -> Result<Response, ContractError> {
+        let (deps, _, info) = ctx;
+        self.check_role(&deps, &info, Role::RoundManager)?;
+
+        let mut round = self.rounds.load(deps.storage, &round_id.to_string())?;
+
+        if round.status != RoundStatus::Voting {
+            return Err(ContractError::RoundNotInVoting { round_id });
+        }
+        if round.voter_count > 0 {
+            return Err(ContractError::VotingStarted { round_id });
+        }
+        // Weights below 1.0 would shrink areas, which vote updates and retractions can't undo
+        let valid = match &weight_curve {
+            WeightCurve::Log2 | WeightCurve::Sqrt => true,
+            WeightCurve::Linear { cap, .. } => *cap >= Decimal::one(),
+            WeightCurve::Piecewise { steps } => {
+                !steps.is_empty()
+                    && steps
+                        .windows(2)
+                        .all(|pair| pair[0].min_score < pair[1].min_score)
+                    && steps.iter().all(|step| step.weight >= Decimal::one())
+            }
+            WeightCurve::Constant { weight } => *weight >= Decimal::one(),
+        };
+        if !valid {
+            return Err(ContractError::InvalidWeightCurve);
+        }
+
+        round.weight_curve = weight_curve;
+        self.rounds
+            .save(deps.storage, &round_id.to_string(), &round)?;
+
+        let resp = Response::new()
+            .add_attribute("action", "set_weight_curve")
+            .add_event(
+                Event::new("set_weight_curve")
+                    .add_attribute("round_id", round_id.to_string())
+                    .add_attribute("weight_curve", format!("{:?}", round.weight_curve)),
+            );
+        Ok(resp)
+    }
+
+    /// Chooses how the weights of a voter's later ballots relate to the first one, only before
+    /// the first vote of the round.
+    #[msg(exec)]
+    pub fn set_weight_policy(
+        &self,
+        ctx: (DepsMut, Env, MessageInfo),
+        round_id: u64,
+        weight_policy: WeightPolicy,
+    ) -> Result<Response, ContractError> {
+        let (deps, _, info) = ctx;
+        self.check_role(&deps, &info, Role::RoundManager)?;
+
+        let mut round = self.rounds.load(deps.storage, &round_id.to_string())?;
+
+        if round.status != RoundStatus::Voting {
+            return Err(ContractError::RoundNotInVoting { round_id });
+        }
+        if round.voter_count > 0 {
+            return Err(ContractError::VotingStarted { round_id });
+        }
+
+        round.weight_policy = weight_policy;
+        self.rounds
+            .save(deps.storage, &round_id.to_string(), &round)?;
+
+        let resp = Response::new()
+            .add_attribute("action", "set_weight_policy")
+            .add_event(
+                Event::new("set_weight_policy")
+                    .add_attribute("round_id", round_id.to_string())
+                    .add_attribute("weight_policy", format!("{:?}", weight_policy)),
+            );
+        Ok(resp)
+    }
+
+    /// Sets the kind of score the round's signer attests in place of vcDORA. Signed ballots
+    /// carry the attested `score` and the round's weight curve maps it to a weight.
+    #[msg(exec)]
+    pub fn set_score_type(
+        &self,
+        ctx: (DepsMut, Env, MessageInfo),
+        round_id: u64,
+        score_type: String,
+    ) -> Result<Response, ContractError> {
+        let (deps, _, info) = ctx;
+        self.check_role(&deps, &info, Role::SignerManager)?;
+
+        let mut round = self.rounds.load(deps.storage, &round_id.to_string())?;
+
+        if round.status != RoundStatus::Voting {
+            return Err(ContractError::RoundNotInVoting { round_id });
+        }
+        if round.voter_count > 0 {
+            return Err(ContractError::VotingStarted { round_id });
+        }
+        if score_type.len() > MAX_SCORE_TYPE_LEN {
+            return Err(ContractError::ScoreTypeTooLong {
+                max: MAX_SCORE_TYPE_LEN,
+            });
         }
 
-        self.admins.save(deps.storage, &admin, &Empty {})?;
+        round.score_type = score_type;
+        self.rounds
+            .save(deps.storage, &round_id.to_string(), &round)?;
 
         let resp = Response::new()
-            .add_attribute("action", "add_member")
-            .add_event(Event::new("admin_added").add_attribute("addr", admin));
+            .add_attribute("action", "set_score_type")
+            .add_event(
+                Event::new("set_score_type")
+                    .add_attribute("round_id", round_id.to_string())
+                    .add_attribute("score_type", round.score_type),
+            );
         Ok(resp)
     }
 
     #[msg(exec)]
-    pub fn del_admin(
+    pub fn set_weight_source(
         &self,
         ctx: (DepsMut, Env, MessageInfo),
-        admin: String,
+        round_id: u64,
+        weight_source: WeightSource,
     ) -> Result<Response, ContractError> {
         let (deps, _, info) = ctx;
-        self.check_owner_permission(&deps, &info)?;
+        self.check_role(&deps, &info, Role::SignerManager)?;
 
-        let admin = deps.api.addr_validate(&admin)?;
-        if !self.admins.has(deps.storage, &admin) {
-            return Err(ContractError::NoAdmins);
+        let mut round = self.rounds.load(deps.storage, &round_id.to_string())?;
+
+        if round.status != RoundStatus::Voting {
+            return Err(ContractError::RoundNotInVoting { round_id });
+        }
+        if round.voter_count > 0 {
+            return Err(ContractError::VotingStarted { round_id });
+        }
+        if let WeightSource::Staking { unit, .. } = weight_source {
+            if unit.is_zero() {
+                return Err(ContractError::StakingUnitZero);
+            }
         }
 
-        self.admins.remove(deps.storage, &admin);
+        round.weight_source = weight_source;
+        self.rounds
+            .save(deps.storage, &round_id.to_string(), &round)?;
 
         let resp = Response::new()
-            .add_attribute("action", "del_admin")
-            .add_event(Event::new("admin_removed").add_attribute("addr", admin));
+            .add_attribute("action", "set_weight_source")
+            .add_event(
+                Event::new("set_weight_source")
+                    .add_attribute("round_id", round_id.to_string())
+                    .add_attribute("weight_source", format!("{:?}", weight_source)),
+            );
         Ok(resp)
     }
 
+    /// Records voters' stakes at round start for rounds weighting from a stake snapshot.
     #[msg(exec)]
-    pub fn start_round(
+    pub fn upload_stake_snapshot(
         &self,
         ctx: (DepsMut, Env, MessageInfo),
-        tax_adjustment_multiplier: u64,
-        donation_denom: String,
-        voting_unit: Uint128,
-        fund: Uint128,
-        pubkey: Vec<u8>,
+        round_id: u64,
+        voters: Vec<String>,
+        amounts: Vec<Uint128>,
     ) -> Result<Response, ContractError> {
         let (deps, _, info) = ctx;
-        self.check_admin_permission(&deps, &info)?;
+        self.check_role(&deps, &info, Role::SignerManager)?;
 
-        let supply = deps
-            .querier
-            .query_supply(&donation_denom)
-            .unwrap_or_default();
-        if supply.amount.is_zero() {
-            return Err(ContractError::InvalidDenom {
-                denom: donation_denom,
+        let round = self.rounds.load(deps.storage, &round_id.to_string())?;
+
+        if round.status != RoundStatus::Voting {
+            return Err(ContractError::RoundNotInVoting { round_id });
+        }
+        if round.voter_count > 0 {
+            return Err(ContractError::VotingStarted { round_id });
+        }
+        if voters.len() != amounts.len() {
+            return Err(ContractError::LengthNotMatch {
+                expected: voters.len() as u128,
+                actual: amounts.len() as u128,
             });
         }
 
-        if voting_unit.u128() == 0 {
-            return Err(ContractError::VotingUnitZero {});
+        for (voter, amount) in voters.iter().zip(amounts.iter()) {
+            let voter = deps.api.addr_validate(voter)?;
+            self.stakes
+                .save(deps.storage, (&round_id.to_string(), &voter), amount)?;
         }
 
-        if pubkey.len() != 65 && pubkey.len() != 0 {
-            return Err(ContractError::InvalidPubkeyLength {});
-        }
+        let resp = Response::new()
+            .add_attribute("action", "upload_stake_snapshot")
+            .add_event(
+                Event::new("upload_stake_snapshot")
+                    .add_attribute("round_id", round_id.to_string())
+                    .add_attribute("voters", voters.len().to_string()),
+            );
+        Ok(resp)
+    }
 
-        let current_round = self.current_round.load(deps.storage)?;
-        let round_id = current_round + 1;
-        self.current_round.save(deps.storage, &round_id)?;
+    #[msg(exec)]
+    pub fn set_payout_mode(
+        &self,
+        ctx: (DepsMut, Env, MessageInfo),
+        round_id: u64,
+        payout_mode: PayoutMode,
+    ) -> Result<Response, ContractError> {
+        let (deps, _, info) = ctx;
+        self.check_role(&deps, &info, Role::RoundManager)?;
 
-        let round = Round {
-            id: round_id,
-            tax_adjustment_multiplier,
-            donation_denom,
-            voting_unit,
-            status: RoundStatus::Voting,
-            fund,
-            project_number: 0,
-            total_area: 0,
-            total_amounts: 0,
-            pubkey,
-        };
+        let mut round = self.rounds.load(deps.storage, &round_id.to_string())?;
+
+        if round.status != RoundStatus::Voting {
+            return Err(ContractError::RoundNotInVoting { round_id });
+        }
 
+        round.payout_mode = payout_mode;
         self.rounds
             .save(deps.storage, &round_id.to_string(), &round)?;
 
         let resp = Response::new()
-            .add_attribute("action", "start_round")
-            .add_event(Event::new("start_round").add_attribute("id", round_id.to_string()));
+            .add_attribute("action", "set_payout_mode")
+            .add_event(
+                Event::new("set_payout_mode")
+                    .add_attribute("round_id", round_id.to_string())
+                    .add_attribute("payout_mode", format!("{:?}", payout_mode)),
+            );
         Ok(resp)
     }
 
+    /// Posts the root of a tree of `(index, owner, amount)` leaves built off-chain from
+    /// the snapshot of the round. `total_amount` is reserved for claims and cannot exceed the
+    /// matching the snapshot allocates.
     #[msg(exec)]
-    pub fn batch_upload_project(
+    pub fn set_merkle_root(
         &self,
         ctx: (DepsMut, Env, MessageInfo),
         round_id: u64,
-        owner_addresses: Vec<String>,
+        merkle_root: String,
+        total_amount: Uint128,
+        claim_deadline: u64,
     ) -> Result<Response, ContractError> {
-        let (deps, _, info) = ctx;
-        self.check_admin_permission(&deps, &info)?;
+        let (deps, env, info) = ctx;
+        self.check_role(&deps, &info, Role::RoundManager)?;
+        self.check_no_approval_required(&deps)?;
 
+        self.apply_set_merkle_root(
+            deps,
+            env,
+            round_id,
+            merkle_root,
+            total_amount,
+            claim_deadline,
+        )
+    }
+
+    fn apply_set_merkle_root(
+        &self,
+        deps: DepsMut,
+        env: Env,
+        round_id: u64,
+        merkle_root: String,
+        total_amount: Uint128,
+        claim_deadline: u64,
+    ) -> Result<Response, ContractError> {
         let mut round = self.rounds.load(deps.storage, &round_id.to_string())?;
 
-        if round.status != RoundStatus::Voting {
-            return Err(ContractError::RoundNotInVoting { round_id: round.id });
+        if !matches!(round.status, RoundStatus::Finished | RoundStatus::Withdrawn) {
+            return Err(ContractError::RoundNotEnded { round_id });
+        }
+        if round.payout_mode != PayoutMode::Merkle {
+            return Err(ContractError::WrongPayoutMode { round_id });
+        }
+        if !round.merkle_root.is_empty() {
+            return Err(ContractError::MerkleRootAlreadySet { round_id });
+        }
+        Self::decode_hash(&merkle_root)?;
+        if total_amount.u128() > round.allocated {
+            return Err(ContractError::InvalidAmount {
+                expected: round.allocated,
+                actual: total_amount.u128(),
+            });
+        }
+        if claim_deadline <= env.block.time.seconds() {
+            return Err(ContractError::ClaimDeadline {
+                round_id,
+                deadline: claim_deadline,
+            });
         }
 
-        owner_addresses.iter().for_each(|addr| {
-            let id = round.project_number + 1;
-            round.project_number = id;
-
-            let project = Project {
-                id,
-                owner: addr.to_string(),
-                area: 0,
-                votes: 0,
-                contribution: 0,
-                status: ProjectStatus::OK,
-            };
-            self.projects
-                .save(
-                    deps.storage,
-                    (&round_id.to_string(), &id.to_string()),
-                    &project,
-                )
-                .unwrap();
-        });
-
+        round.merkle_root = merkle_root.to_lowercase();
+        round.merkle_total = total_amount.u128();
+        round.claim_deadline = claim_deadline;
         self.rounds
-            .save(deps.storage, &round_id.to_string(), &round)
-            .unwrap();
+            .save(deps.storage, &round_id.to_string(), &round)?;
 
         let resp = Response::new()
-            .add_attribute("action", "batch_upload_project")
+            .add_attribute("action", "set_merkle_root")
             .add_event(
-                Event::new("batch_upload_project")
+                Event::new("set_merkle_root")
                     .add_attribute("round_id", round_id.to_string())
-                    .add_attribute("projects", owner_addresses.join(", ").to_string()),
+                    .add_attribute("merkle_root", round.merkle_root)
+                    .add_attribute("total_amount", total_amount.to_string())
+                    .add_attribute("claim_deadline", claim_deadline.to_string()),
             );
         Ok(resp)
     }
 
     #[msg(exec)]
-    pub fn weighted_batch_vote(
+    pub fn claim_merkle(
         &self,
         ctx: (DepsMut, Env, MessageInfo),
         round_id: u64,
-        project_ids: Vec<u64>,
-        amounts: Vec<Uint128>,
-        vcdora: u64,
-        timestamp: u64,
-        recid: u8,
-        sig: Vec<u8>,
-        sig_chain_id: String,
-        sig_contract_addr: String,
+        index: u64,
+        amount: Uint128,
+        proof: Vec<String>,
     ) -> Result<Response, ContractError> {
         let (deps, env, info) = ctx;
 
         let mut round = self.rounds.load(deps.storage, &round_id.to_string())?;
+        if round.merkle_root.is_empty() {
+            return Err(ContractError::MerkleRootNotSet { round_id });
+        }
+        if env.block.time.seconds() >= round.claim_deadline {
+            return Err(ContractError::ClaimDeadline {
+                round_id,
+                deadline: round.claim_deadline,
+            });
+        }
+        self.check_not_paused(&deps, &round)?;
 
-        if round.status != RoundStatus::Voting {
-            return Err(ContractError::RoundNotInVoting { round_id });
+        let word = self
+            .merkle_claims
+            .may_load(deps.storage, (&round_id.to_string(), index / 64))?
+            .unwrap_or_default();
+        let bit = 1u64 << (index % 64);
+        if word & bit != 0 {
+            return Err(ContractError::AlreadyClaimed { index });
         }
 
-        if project_ids.len() != amounts.len() {
-            return Err(ContractError::LengthNotMatch {
-                expected: project_ids.len() as u128,
-                actual: amounts.len() as u128,
+        let root = Self::decode_hash(&round.merkle_root)?;
+        let proof = proof
+            .iter()
+            .map(|hash| Self::decode_hash(hash))
+            .collect::<Result<Vec<_>, _>>()?;
+        let leaf = merkle::leaf_hash(index, info.sender.as_str(), amount.u128());
+        if !merkle::verify(&root, leaf, &proof) {
+            return Err(ContractError::InvalidMerkleProof);
+        }
+
+        let amounts = amount.u128();
+        if round.merkle_claimed + amounts > round.merkle_total {
+            return Err(ContractError::InvalidAmount {
+                expected: round.merkle_total - round.merkle_claimed,
+                actual: amounts,
             });
         }
+        Self::release_matching(&mut round, amounts)?;
+        round.merkle_claimed += amounts;
+        self.rounds
+            .save(deps.storage, &round_id.to_string(), &round)?;
+        self.merkle_claims.save(
+            deps.storage,
+            (&round_id.to_string(), index / 64),
+            &(word | bit),
+        )?;
 
-        let weight: u64;
-        if sig.is_empty() {
-            // If there is no signature, the weight is 1.0, which means vcDORA is not included in the calculation.
-            weight = 10;
-        } else if sig.len() != 64 {
-            return Err(ContractError::InvalidSignatureLength {});
-        } else {
-            if round.pubkey.is_empty() {
-                return Err(ContractError::PubkeyNotSet {});
-            }
-            // buidl msg
-            let addr = deps.api.addr_canonicalize(info.sender.as_str()).unwrap();
-            let addr_bytes = addr.as_slice();
-            let msg = signature::build_msg(
-                addr_bytes,
-                round_id,
-                &project_ids,
-                &amounts,
-                vcdora,
-                timestamp,
-                &sig_chain_id,
-                &sig_contract_addr,
+        let message = BankMsg::Send {
+            to_address: info.sender.to_string(),
+            amount: coins(amounts, &round.donation_denom),
+        };
+        let resp = Response::new()
+            .add_message(message)
+            .add_attribute("action", "claim_merkle")
+            .add_event(
+                Event::new("claim_merkle")
+                    .add_attribute("round_id", round_id.to_string())
+                    .add_attribute("index", index.to_string())
+                    .add_attribute("owner", info.sender)
+                    .add_attribute("amounts", amounts.to_string()),
             );
+        Ok(resp)
+    }
 
-            // verify signature
-            if env.block.time.seconds() > timestamp + 60 * 60 {
-                // 1 hour
-                return Err(ContractError::InvalidSignatureTimestamp {});
-            }
-            let pubkey = signature::recover_pubkey(deps.as_ref(), msg, sig, recid);
-            if pubkey != round.pubkey {
-                return Err(ContractError::InvalidSignature {});
-            }
-
-            // calculate weight, 10 means 1.0
-            weight = math::log2_u64_with_decimal(vcdora + 2)?; // plus 2 to avoid 0
-        }
+    /// Sends Merkle allocations nobody claimed before the deadline to the treasury.
+    #[msg(exec)]
+    pub fn sweep_unclaimed(
+        &self,
+        ctx: (DepsMut, Env, MessageInfo),
+        round_id: u64,
+    ) -> Result<Response, ContractError> {
+        let (deps, env, info) = ctx;
+        self.check_role(&deps, &info, Role::Treasurer)?;
+        self.check_no_approval_required(&deps)?;
 
-        let mut total_amounts = 0;
-        let mut total_area = 0;
+        self.apply_sweep_unclaimed(deps, env, round_id)
+    }
 
-        let denom = round.donation_denom.clone();
+    fn apply_sweep_unclaimed(
+        &self,
+        deps: DepsMut,
+        env: Env,
+        round_id: u64,
+    ) -> Result<Response, ContractError> {
+        let mut round = self.rounds.load(deps.storage, &round_id.to_string())?;
+        if round.merkle_root.is_empty() {
+            return Err(ContractError::MerkleRootNotSet { round_id });
+        }
+        if env.block.time.seconds() < round.claim_deadline {
+            return Err(ContractError::ClaimDeadline {
+                round_id,
+                deadline: round.claim_deadline,
+            });
+        }
+        self.check_not_paused(&deps, &round)?;
 
-        let decimals = if denom == "inj" {
-            INJ_DECIMALS // hardcode for INJ
+        let amounts = if round.merkle_swept {
+            0
         } else {
-            let denom_metadata = deps.querier.query_denom_metadata(&denom).unwrap();
-
-            let mut denom_unit: Option<DenomUnit> = None;
-            for unit in denom_metadata.denom_units {
-                if unit.denom == denom_metadata.display {
-                    denom_unit = Some(unit);
-                    break;
-                }
-            }
-            denom_unit.unwrap().exponent
+            round.merkle_total - round.merkle_claimed
         };
+        Self::release_matching(&mut round, amounts)?;
+        round.merkle_swept = true;
+        self.rounds
+            .save(deps.storage, &round_id.to_string(), &round)?;
+        let treasury = self.treasury((deps.as_ref(), env))?.treasury;
 
-        for (project_id, vote) in project_ids.iter().zip(amounts.iter()) {
-            let amount = vote.u128();
-            total_amounts = total_amounts + amount;
-            let mut project = self.projects.load(
-                deps.storage,
-                (&round_id.to_string(), &project_id.to_string()),
-            )?;
+        let resp = if amounts > 0 {
+            let message = BankMsg::Send {
+                to_address: treasury.clone(),
+                amount: coins(amounts, &round.donation_denom),
+            };
 
-            let pow_10_decimals = 10u128.pow(decimals);
-            let votes = amount * round.voting_unit.u128() / pow_10_decimals;
-            if votes == 0 {
-                return Err(ContractError::TooSmallAmount { amount });
-            }
+            Response::new().add_message(message)
+        } else {
+            Response::new()
+        };
 
-            project.votes = project.votes + votes;
-            project.contribution = project.contribution + amount;
+        let resp = resp.add_attribute("action", "sweep_unclaimed").add_event(
+            Event::new("sweep_unclaimed")
+                .add_attribute("round_id", round_id.to_string())
+                .add_attribute("treasury", treasury)
+                .add_attribute("amounts", amounts.to_string()),
+        );
+        Ok(resp)
+    }
 
-            // Compute area difference and update project/round area
-            let mut old_votes: u128 = 0;
-            let mut new_votes: u128 = votes;
+    /// Switches the round to vested payouts, `cliff` and `duration` are in seconds from finalization.
+    #[msg(exec)]
+    pub fn set_vesting(
+        &self,
+        ctx: (DepsMut, Env, MessageInfo),
+        round_id: u64,
+        cliff: u64,
+        duration: u64,
+    ) -> Result<Response, ContractError> {
+        let (deps, _, info) = ctx;
+        self.check_role(&deps, &info, Role::RoundManager)?;
 
-            let votes = self.votes.may_load(
-                deps.storage,
-                (&round_id.to_string(), &project_id.to_string(), &info.sender),
-            )?;
-            match votes {
-                Some(votes) => {
-                    old_votes = votes;
-                    new_votes = new_votes + old_votes;
-                }
-                None => {}
-            }
-            deps.api.debug(&format!(
-                "old_votes: {} new_votes: {}",
-                old_votes, new_votes
-            ));
-            self.votes.save(
-                deps.storage,
-                (&round_id.to_string(), &project_id.to_string(), &info.sender),
-                &new_votes,
-            )?;
+        let mut round = self.rounds.load(deps.storage, &round_id.to_string())?;
 
-            let old_area = math::sqrt(old_votes * 100); // times 100 to avoid float, scale area by 10
-            let new_area = math::sqrt(new_votes * 100);
-            deps.api
-                .debug(&format!("old_area: {} new_area: {}", old_area, new_area));
+        if round.status != RoundStatus::Voting {
+            return Err(ContractError::RoundNotInVoting { round_id });
+        }
+        if cliff > duration {
+            return Err(ContractError::InvalidVestingSchedule { cliff, duration });
+        }
 
-            let area_diff = (new_area * weight / 10 - old_area) as u128; // adjust by weight, 10 means 1.0, div 10 to get the real weight
+        round.payout_mode = PayoutMode::Vested;
+        round.vesting_cliff = cliff;
+        round.vesting_duration = duration;
+        self.rounds
+            .save(deps.storage, &round_id.to_string(), &round)?;
 
-            project.area = project.area + area_diff;
-            total_area = total_area + area_diff;
-            deps.api
-                .debug(&format!("total_area inner: {} {}", total_area, area_diff));
+        let resp = Response::new()
+            .add_attribute("action", "set_vesting")
+            .add_event(
+                Event::new("set_vesting")
+                    .add_attribute("round_id", round_id.to_string())
+                    .add_attribute("cliff", cliff.to_string())
+                    .add_attribute("duration", duration.to_string()),
+            );
+        Ok(resp)
+    }
 
-            self.projects.save(
-                deps.storage,
-                (&round_id.to_string(), &project_id.to_string()),
-                &project,
-            )?;
+    #[msg(exec)]
+    pub fn claim_vested(
+        &self,
+        ctx: (DepsMut, Env, MessageInfo),
+        round_id: u64,
+        project_id: u64,
+    ) -> Result<Response, ContractError> {
+        let (deps, env, info) = ctx;
+
+        let mut round = self.rounds.load(deps.storage, &round_id.to_string())?;
+        if !matches!(round.status, RoundStatus::Finished | RoundStatus::Withdrawn) {
+            return Err(ContractError::RoundNotEnded { round_id });
         }
-        let denom = round.donation_denom.clone();
-        let transfer = cw_utils::must_pay(&info, &denom)
-            .map_err(|err| StdError::generic_err(err.to_string()))?
-            .u128();
-        if transfer != total_amounts {
-            return Err(ContractError::InvalidAmount {
-                expected: total_amounts,
-                actual: transfer,
+        if round.payout_mode != PayoutMode::Vested {
+            return Err(ContractError::WrongPayoutMode { round_id });
+        }
+        self.check_not_paused(&deps, &round)?;
+
+        let key = (round_id.to_string(), project_id.to_string());
+        let grant = self
+            .grants
+            .may_load(deps.storage, (&key.0, &key.1))?
+            .ok_or(ContractError::ProjectNotExist { project_id })?;
+        if info.sender != grant.owner {
+            return Err(ContractError::Unauthorized {
+                sender: info.sender,
             });
         }
 
-        round.total_area += total_area;
-        round.total_amounts += total_amounts;
+        let at = self
+            .vesting_halts
+            .may_load(deps.storage, (&key.0, &key.1))?
+            .unwrap_or(env.block.time.seconds());
+        let vested = Self::vested_amount(&round, grant.matching, at);
+        let claimed = self
+            .claims
+            .may_load(deps.storage, (&key.0, &key.1))?
+            .unwrap_or_default();
+        let amounts = vested.saturating_sub(claimed);
+        if amounts == 0 {
+            return Err(ContractError::NothingToClaim { project_id });
+        }
+        Self::release_matching(&mut round, amounts)?;
         self.rounds
             .save(deps.storage, &round_id.to_string(), &round)?;
+        self.claims.save(deps.storage, (&key.0, &key.1), &vested)?;
 
+        let message = BankMsg::Send {
+            to_address: grant.owner.clone(),
+            amount: coins(amounts, &round.donation_denom),
+        };
         let resp = Response::new()
-            .add_attribute("action", "weighted_batch_vote")
+            .add_message(message)
+            .add_attribute("action", "claim_vested")
             .add_event(
-                Event::new("weighted_batch_vote")
-                    .add_attribute("voter", info.sender)
+                Event::new("claim_vested")
                     .add_attribute("round_id", round_id.to_string())
-                    .add_attribute("vcdora", vcdora.to_string())
-                    .add_attribute(
-                        "projects",
-                        format!(
-                            "{:?}",
-                            project_ids
-                                .iter()
-                                .map(|x| x.to_string())
-                                .collect::<Vec<String>>()
-                                .join(",")
-                        ),
-                    )
-                    .add_attribute(
-                        "amounts",
-                        format!(
-                            "{:?}",
-                            amounts
-                                .iter()
-                                .map(|x| x.to_string())
-                                .collect::<Vec<String>>()
-                                .join(",")
-                        ),
-                    )
-                    .add_attribute("total_area", total_area.to_string()),
+                    .add_attribute("project_id", project_id.to_string())
+                    .add_attribute("owner", grant.owner)
+                    .add_attribute("amounts", amounts.to_string()),
             );
         Ok(resp)
     }
 
+    /// Stops further vesting for a project; the unvested remainder goes back to the treasury.
     #[msg(exec)]
-    pub fn end_round(
+    pub fn halt_vesting(
         &self,
         ctx: (DepsMut, Env, MessageInfo),
         round_id: u64,
+        project_id: u64,
     ) -> Result<Response, ContractError> {
-        let (deps, _, info) = ctx;
-        self.check_admin_permission(&deps, &info)?;
+        let (deps, env, info) = ctx;
+        self.check_role(&deps, &info, Role::ProjectCurator)?;
+        self.check_no_approval_required(&deps)?;
+
+        self.apply_halt_vesting(deps, env, round_id, project_id)
+    }
 
+    fn apply_halt_vesting(
+        &self,
+        deps: DepsMut,
+        env: Env,
+        round_id: u64,
+        project_id: u64,
+    ) -> Result<Response, ContractError> {
         let mut round = self.rounds.load(deps.storage, &round_id.to_string())?;
+        if !matches!(round.status, RoundStatus::Finished | RoundStatus::Withdrawn) {
+            return Err(ContractError::RoundNotEnded { round_id });
+        }
+        if round.payout_mode != PayoutMode::Vested {
+            return Err(ContractError::WrongPayoutMode { round_id });
+        }
+        self.check_not_paused(&deps, &round)?;
 
-        if round.status != RoundStatus::Voting {
-            return Err(ContractError::RoundNotInVoting { round_id });
+        let key = (round_id.to_string(), project_id.to_string());
+        let grant = self
+            .grants
+            .may_load(deps.storage, (&key.0, &key.1))?
+            .ok_or(ContractError::ProjectNotExist { project_id })?;
+        if self.vesting_halts.has(deps.storage, (&key.0, &key.1)) {
+            return Err(ContractError::VestingHalted { project_id });
         }
 
-        round.status = RoundStatus::Finished;
+        let now = env.block.time.seconds();
+        let forfeited = grant.matching - Self::vested_amount(&round, grant.matching, now);
+        Self::release_matching(&mut round, forfeited)?;
         self.rounds
             .save(deps.storage, &round_id.to_string(), &round)?;
+        self.vesting_halts
+            .save(deps.storage, (&key.0, &key.1), &now)?;
+        let treasury = self.treasury((deps.as_ref(), env))?.treasury;
+
+        let resp = if forfeited > 0 {
+            let message = BankMsg::Send {
+                to_address: treasury.clone(),
+                amount: coins(forfeited, &round.donation_denom),
+            };
+
+            Response::new().add_message(message)
+        } else {
+            Response::new()
+        };
+
+        let resp = resp.add_attribute("action", "halt_vesting").add_event(
+            Event::new("halt_vesting")
+                .add_attribute("round_id", round_id.to_string())
+                .add_attribute("project_id", project_id.to_string())
+                .add_attribute("treasury", treasury)
+                .add_attribute("forfeited", forfeited.to_string()),
+        );
+        Ok(resp)
+    }
+
+    #[msg(exec)]
+    pub fn add_milestone(
+        &self,
+        ctx: (DepsMut, Env, MessageInfo),
+        round_id: u64,
+        project_id: u64,
+        description: String,
+        percentage: u64,
+    ) -> Result<Response, ContractError> {
+        let (deps, _, info) = ctx;
+        self.check_role(&deps, &info, Role::ProjectCurator)?;
+
+        let round = self.rounds.load(deps.storage, &round_id.to_string())?;
+        self.load_milestone_grant(&deps, &round, project_id)?;
+
+        let key = (round_id.to_string(), project_id.to_string());
+        let mut milestones = self
+            .milestones
+            .may_load(deps.storage, (&key.0, &key.1))?
+            .unwrap_or_default();
+        let total = milestones
+            .iter()
+            .map(|milestone| milestone.percentage)
+            .sum::<u64>()
+            + percentage;
+        if percentage == 0 || total > 100 {
+            return Err(ContractError::InvalidMilestonePercentage { total });
+        }
+
+        let id = milestones.len() as u64 + 1;
+        milestones.push(Milestone {
+            id,
+            description,
+            percentage,
+            completed: false,
+        });
+        self.milestones
+            .save(deps.storage, (&key.0, &key.1), &milestones)?;
 
         let resp = Response::new()
-            .add_attribute("action", "end_round")
-            .add_event(Event::new("end_round").add_attribute("round_id", round_id.to_string()));
+            .add_attribute("action", "add_milestone")
+            .add_event(
+                Event::new("add_milestone")
+                    .add_attribute("round_id", round_id.to_string())
+                    .add_attribute("project_id", project_id.to_string())
+                    .add_attribute("milestone_id", id.to_string())
+                    .add_attribute("percentage", percentage.to_string()),
+            );
         Ok(resp)
     }
 
     #[msg(exec)]
-    pub fn set_pubkey(
+    pub fn complete_milestone(
         &self,
         ctx: (DepsMut, Env, MessageInfo),
         round_id: u64,
-        pubkey: Vec<u8>,
+        project_id: u64,
+        milestone_id: u64,
     ) -> Result<Response, ContractError> {
         let (deps, _, info) = ctx;
-        self.check_admin_permission(&deps, &info)?;
+        self.check_role(&deps, &info, Role::ProjectCurator)?;
 
-        if pubkey.len() != 65 {
-            return Err(ContractError::InvalidPubkeyLength {});
+        let round = self.rounds.load(deps.storage, &round_id.to_string())?;
+        if !matches!(round.status, RoundStatus::Finished | RoundStatus::Withdrawn) {
+            return Err(ContractError::RoundNotEnded { round_id });
         }
+        self.load_milestone_grant(&deps, &round, project_id)?;
+
+        let key = (round_id.to_string(), project_id.to_string());
+        let mut milestones = self
+            .milestones
+            .may_load(deps.storage, (&key.0, &key.1))?
+            .unwrap_or_default();
+        let milestone = milestones
+            .iter_mut()
+            .find(|milestone| milestone.id == milestone_id)
+            .ok_or(ContractError::MilestoneNotExist { id: milestone_id })?;
+        if milestone.completed {
+            return Err(ContractError::MilestoneCompleted { id: milestone_id });
+        }
+        milestone.completed = true;
+        self.milestones
+            .save(deps.storage, (&key.0, &key.1), &milestones)?;
+
+        let resp = Response::new()
+            .add_attribute("action", "complete_milestone")
+            .add_event(
+                Event::new("complete_milestone")
+                    .add_attribute("round_id", round_id.to_string())
+                    .add_attribute("project_id", project_id.to_string())
+                    .add_attribute("milestone_id", milestone_id.to_string()),
+            );
+        Ok(resp)
+    }
+
+    #[msg(exec)]
+    pub fn claim_milestones(
+        &self,
+        ctx: (DepsMut, Env, MessageInfo),
+        round_id: u64,
+        project_id: u64,
+    ) -> Result<Response, ContractError> {
+        let (deps, _, info) = ctx;
 
         let mut round = self.rounds.load(deps.storage, &round_id.to_string())?;
+        if !matches!(round.status, RoundStatus::Finished | RoundStatus::Withdrawn) {
+            return Err(ContractError::RoundNotEnded { round_id });
+        }
+        if round.payout_mode != PayoutMode::Milestone {
+            return Err(ContractError::WrongPayoutMode { round_id });
+        }
+        self.check_not_paused(&deps, &round)?;
 
-        if round.status != RoundStatus::Voting {
-            return Err(ContractError::RoundNotInVoting { round_id });
+        let key = (round_id.to_string(), project_id.to_string());
+        let grant = self
+            .grants
+            .may_load(deps.storage, (&key.0, &key.1))?
+            .ok_or(ContractError::ProjectNotExist { project_id })?;
+        if info.sender != grant.owner {
+            return Err(ContractError::Unauthorized {
+                sender: info.sender,
+            });
         }
 
-        round.pubkey = pubkey.clone();
+        let milestones = self
+            .milestones
+            .may_load(deps.storage, (&key.0, &key.1))?
+            .unwrap_or_default();
+        let released = Self::milestone_released(grant.matching, &milestones);
+        let claimed = self
+            .claims
+            .may_load(deps.storage, (&key.0, &key.1))?
+            .unwrap_or_default();
+        let amounts = released.saturating_sub(claimed);
+        if amounts == 0 {
+            return Err(ContractError::NothingToClaim { project_id });
+        }
+        Self::release_matching(&mut round, amounts)?;
         self.rounds
             .save(deps.storage, &round_id.to_string(), &round)?;
+        self.claims
+            .save(deps.storage, (&key.0, &key.1), &released)?;
 
+        let message = BankMsg::Send {
+            to_address: grant.owner.clone(),
+            amount: coins(amounts, &round.donation_denom),
+        };
         let resp = Response::new()
-            .add_attribute("action", "set_pubkey")
+            .add_message(message)
+            .add_attribute("action", "claim_milestones")
             .add_event(
-                Event::new("set_pubkey").add_attribute("pubkey", hex::encode(&pubkey.as_slice())),
+                Event::new("claim_milestones")
+                    .add_attribute("round_id", round_id.to_string())
+                    .add_attribute("project_id", project_id.to_string())
+                    .add_attribute("owner", grant.owner)
+                    .add_attribute("amounts", amounts.to_string()),
             );
         Ok(resp)
     }
 
+    /// Closes a project's milestone plan. Matching not unlocked so far goes to the fund of
+    /// `next_round_id` when given, otherwise back to the treasury.
     #[msg(exec)]
-    pub fn withdraw(
+    pub fn close_milestones(
         &self,
         ctx: (DepsMut, Env, MessageInfo),
         round_id: u64,
+        project_id: u64,
+        next_round_id: Option<u64>,
     ) -> Result<Response, ContractError> {
-        let (deps, _, info) = ctx;
-        self.check_admin_permission(&deps, &info)?;
+        let (deps, env, info) = ctx;
+        self.check_role(&deps, &info, Role::Treasurer)?;
+        self.check_no_approval_required(&deps)?;
 
-        let mut round = self.rounds.load(deps.storage, &round_id.to_string())?;
-        let denom = round.donation_denom.clone();
+        self.apply_close_milestones(deps, env, round_id, project_id, next_round_id)
+    }
 
-        if round.status != RoundStatus::Finished {
+    fn apply_close_milestones(
+        &self,
+        mut deps: DepsMut,
+        env: Env,
+        round_id: u64,
+        project_id: u64,
+        next_round_id: Option<u64>,
+    ) -> Result<Response, ContractError> {
+        let mut round = self.rounds.load(deps.storage, &round_id.to_string())?;
+        if !matches!(round.status, RoundStatus::Finished | RoundStatus::Withdrawn) {
             return Err(ContractError::RoundNotEnded { round_id });
         }
+        self.check_not_paused(&deps, &round)?;
+        let grant = self.load_milestone_grant(&deps, &round, project_id)?;
 
-        round.status = RoundStatus::Withdrawn;
+        let key = (round_id.to_string(), project_id.to_string());
+        let milestones = self
+            .milestones
+            .may_load(deps.storage, (&key.0, &key.1))?
+            .unwrap_or_default();
+        let returned = grant.matching - Self::milestone_released(grant.matching, &milestones);
+        Self::release_matching(&mut round, returned)?;
         self.rounds
             .save(deps.storage, &round_id.to_string(), &round)?;
+        self.milestone_returns
+            .save(deps.storage, (&key.0, &key.1), &returned)?;
 
-        let amounts = round.total_amounts;
-        let resp = if amounts > 0 {
-            let message = BankMsg::Send {
-                to_address: info.sender.to_string(),
-                amount: coins(amounts, &denom),
-            };
+        let mut event = Event::new("close_milestones")
+            .add_attribute("round_id", round_id.to_string())
+            .add_attribute("project_id", project_id.to_string())
+            .add_attribute("returned", returned.to_string());
+        let mut resp = Response::new();
+        if let Some(next_round_id) = next_round_id {
+            self.fund_next_round(&mut deps, &round, next_round_id, returned)?;
+            event = event.add_attribute("next_round_id", next_round_id.to_string());
+        } else {
+            let treasury = self.treasury((deps.as_ref(), env))?.treasury;
+            if returned > 0 {
+                resp = resp.add_message(BankMsg::Send {
+                    to_address: treasury.clone(),
+                    amount: coins(returned, &round.donation_denom),
+                });
+            }
+            event = event.add_attribute("treasury", treasury);
+        }
 
-            Response::new().add_message(message)
+        Ok(resp
+            .add_attribute("action", "close_milestones")
+            .add_event(event))
+    }
+
+    /// Moves matching nobody can receive anymore into the fund of a later round: the part of
+    /// the fund not allocated to any project plus grants (direct payouts) or leaves (Merkle
+    /// payouts) not claimed before the deadline.
+    #[msg(exec)]
+    pub fn rollover(
+        &self,
+        ctx: (DepsMut, Env, MessageInfo),
+        round_id: u64,
+        next_round_id: u64,
+    ) -> Result<Response, ContractError> {
+        let (deps, env, info) = ctx;
+        self.check_role(&deps, &info, Role::Treasurer)?;
+        self.check_no_approval_required(&deps)?;
+
+        self.apply_rollover(deps, env, round_id, next_round_id)
+    }
+
+    fn apply_rollover(
+        &self,
+        mut deps: DepsMut,
+        env: Env,
+        round_id: u64,
+        next_round_id: u64,
+    ) -> Result<Response, ContractError> {
+        let mut round = self.rounds.load(deps.storage, &round_id.to_string())?;
+        if !matches!(round.status, RoundStatus::Finished | RoundStatus::Withdrawn) {
+            return Err(ContractError::RoundNotEnded { round_id });
+        }
+        if round.rollover_to != 0 {
+            return Err(ContractError::RoundRolledOver { round_id });
+        }
+        self.check_not_paused(&deps, &round)?;
+        if round.payout_mode == PayoutMode::Direct {
+            if round.claim_deadline == 0 {
+                return Err(ContractError::ClaimDeadlineNotSet { round_id });
+            }
+            if env.block.time.seconds() < round.claim_deadline {
+                return Err(ContractError::ClaimDeadline {
+                    round_id,
+                    deadline: round.claim_deadline,
+                });
+            }
+        }
+
+        let (unallocated, unclaimed) = if round.payout_mode == PayoutMode::Merkle {
+            if round.merkle_root.is_empty() {
+                return Err(ContractError::MerkleRootNotSet { round_id });
+            }
+            if env.block.time.seconds() < round.claim_deadline {
+                return Err(ContractError::ClaimDeadline {
+                    round_id,
+                    deadline: round.claim_deadline,
+                });
+            }
+            let unclaimed = if round.merkle_swept {
+                0
+            } else {
+                round.merkle_total - round.merkle_claimed
+            };
+            round.merkle_swept = true;
+            (round.fund.u128() - round.merkle_total, unclaimed)
         } else {
-            Response::new()
+            let mut allocated = 0u128;
+            let mut unclaimed = 0u128;
+            let grants = self
+                .grants
+                .prefix(&round_id.to_string())
+                .range(deps.storage, None, None, Order::Ascending)
+                .collect::<StdResult<Vec<_>>>()?;
+            for (project_id, grant) in grants {
+                allocated += grant.matching;
+                if round.payout_mode == PayoutMode::Direct {
+                    let claimed = self
+                        .claims
+                        .may_load(deps.storage, (&round_id.to_string(), &project_id))?
+                        .unwrap_or_default();
+                    unclaimed += grant.matching - claimed;
+                }
+            }
+            (round.fund.u128() - allocated, unclaimed)
         };
+        let amounts = unallocated + unclaimed;
+        Self::release_matching(&mut round, amounts)?;
 
-        resp.clone().add_attribute("action", "withdraw").add_event(
-            Event::new("withdraw")
-                .add_attribute("round_id", round_id.to_string())
-                .add_attribute("amounts", amounts.to_string()),
-        );
+        self.fund_next_round(&mut deps, &round, next_round_id, amounts)?;
+        round.rollover_to = next_round_id;
+        round.rolled_over = amounts;
+        self.rounds
+            .save(deps.storage, &round_id.to_string(), &round)?;
+
+        let resp = Response::new()
+            .add_attribute("action", "rollover")
+            .add_event(
+                Event::new("rollover")
+                    .add_attribute("round_id", round_id.to_string())
+                    .add_attribute("next_round_id", next_round_id.to_string())
+                    .add_attribute("unallocated", unallocated.to_string())
+                    .add_attribute("unclaimed", unclaimed.to_string())
+                    .add_attribute("amounts", amounts.to_string()),
+            );
         Ok(resp)
     }
 }
@@ -2,6 +2,8 @@ use cosmwasm_std::{Addr, StdError};
 use cw_utils::PaymentError;
 use thiserror::Error;
 
+use crate::state::Role;
+
 #[derive(Error, Debug, PartialEq)]
 pub enum ContractError {
     // Add any other custom errors you like here.
@@ -18,12 +20,33 @@ pub enum ContractError {
     #[error("{address} is already an admin")]
     NoDupAddress { address: Addr },
 
+    #[error("{sender} does not have the {role} role")]
+    MissingRole { sender: Addr, role: Role },
+
+    #[error("{address} already has the {role} role")]
+    RoleAlreadyGranted { address: Addr, role: Role },
+
+    #[error("{address} does not have the {role} role")]
+    RoleNotGranted { address: Addr, role: Role },
+
     #[error("{round_id} is not in voting status")]
     RoundNotInVoting { round_id: u64 },
 
     #[error("{round_id} is not ended")]
     RoundNotEnded { round_id: u64 },
 
+    #[error("{round_id} is not in challenging status")]
+    RoundNotChallenging { round_id: u64 },
+
+    #[error("Challenge period of {round_id} ends at {ends_at}")]
+    ChallengePeriodNotOver { round_id: u64, ends_at: u64 },
+
+    #[error("Results of {round_id} are frozen")]
+    SnapshotFrozen { round_id: u64 },
+
+    #[error("Project {project_id} does not exist")]
+    ProjectNotExist { project_id: u64 },
+
     #[error("{round_id} does not exist")]
     RoundNotExist { round_id: u64 },
 
@@ -59,4 +82,147 @@ pub enum ContractError {
 
     #[error("Amount too small: {amount}")]
     TooSmallAmount { amount: u128 },
+
+    #[error("Donation {amount} is below the round minimum of {min}")]
+    DonationBelowMinimum { amount: u128, min: u128 },
+
+    #[error("Donations to project {project_id} would exceed the per-voter cap of {cap}")]
+    ProjectDonationCapExceeded { project_id: u64, cap: u128 },
+
+    #[error("Donations would exceed the per-voter round cap of {cap}")]
+    VoterDonationCapExceeded { cap: u128 },
+
+    #[error("Contract is paused")]
+    Paused,
+
+    #[error("{round_id} is paused")]
+    RoundPaused { round_id: u64 },
+
+    #[error("Cannot withdraw {requested}, only {available} left")]
+    WithdrawExceedsAvailable { available: u128, requested: u128 },
+
+    #[error("Invalid fee: {fee_bps} bps")]
+    InvalidFeeBps { fee_bps: u64 },
+
+    #[error("Queued action {id} does not exist")]
+    QueuedActionNotExist { id: u64 },
+
+    #[error("Queued action {id} cannot be executed before {eta}")]
+    TimelockNotExpired { id: u64, eta: u64 },
+
+    #[error("This action requires {threshold} admin approvals, submit a proposal instead")]
+    ApprovalRequired { threshold: u64 },
+
+    #[error("Invalid approval threshold {threshold} for {admins} admins")]
+    InvalidThreshold { threshold: u64, admins: u64 },
+
+    #[error("Proposal {id} does not exist")]
+    ProposalNotExist { id: u64 },
+
+    #[error("Proposal {id} is already executed")]
+    ProposalExecuted { id: u64 },
+
+    #[error("{address} already approved proposal {id}")]
+    AlreadyApproved { id: u64, address: Addr },
+
+    #[error("Proposal {id} has {approvals} of the {threshold} required approvals")]
+    NotEnoughApprovals {
+        id: u64,
+        approvals: u64,
+        threshold: u64,
+    },
+
+    #[error("Nothing to claim for project {project_id}")]
+    NothingToClaim { project_id: u64 },
+
+    #[error("{round_id} does not use this payout mode")]
+    WrongPayoutMode { round_id: u64 },
+
+    #[error("Merkle root of {round_id} is already set")]
+    MerkleRootAlreadySet { round_id: u64 },
+
+    #[error("Merkle root of {round_id} is not set")]
+    MerkleRootNotSet { round_id: u64 },
+
+    #[error("Invalid merkle root or proof")]
+    InvalidMerkleProof,
+
+    #[error("Leaf {index} is already claimed")]
+    AlreadyClaimed { index: u64 },
+
+    #[error("Claim deadline of {round_id} is {deadline}")]
+    ClaimDeadline { round_id: u64, deadline: u64 },
+
+    #[error("Claim deadline of {round_id} is not set")]
+    ClaimDeadlineNotSet { round_id: u64 },
+
+    #[error("Vesting cliff {cliff} exceeds duration {duration}")]
+    InvalidVestingSchedule { cliff: u64, duration: u64 },
+
+    #[error("Vesting of project {project_id} is halted")]
+    VestingHalted { project_id: u64 },
+
+    #[error("Milestone percentages would add up to {total}%")]
+    InvalidMilestonePercentage { total: u64 },
+
+    #[error("Milestone {id} does not exist")]
+    MilestoneNotExist { id: u64 },
+
+    #[error("Milestone {id} is already completed")]
+    MilestoneCompleted { id: u64 },
+
+    #[error("Milestones of project {project_id} are closed")]
+    MilestonesClosed { project_id: u64 },
+
+    #[error("Only {remaining} of the fund of {round_id} is left to deposit")]
+    DepositExceedsFund { round_id: u64, remaining: u128 },
+
+    #[error("{round_id} has {deposited} of its {fund} fund deposited")]
+    RoundNotFunded {
+        round_id: u64,
+        fund: u128,
+        deposited: u128,
+    },
+
+    #[error("Payouts of {round_id} would exceed the {deposited} deposited")]
+    MatchingExceedsDeposit { round_id: u64, deposited: u128 },
+
+    #[error("Leftover matching of {round_id} is already rolled over")]
+    RoundRolledOver { round_id: u64 },
+
+    #[error("Invalid matching strategy parameters")]
+    InvalidMatchingStrategy,
+
+    #[error("Matching strategy of {round_id} does not support this")]
+    WrongMatchingStrategy { round_id: u64 },
+
+    #[error("Pairwise rounds allow at most {limit} projects")]
+    PairwiseProjectsExceeded { round_id: u64, limit: u64 },
+
+    #[error("Pairwise rounds allow at most {limit} voters on project {project_id}")]
+    PairwiseVotersExceeded { project_id: u64, limit: u64 },
+
+    #[error("Invalid precision: {precision}")]
+    InvalidPrecision { precision: u32 },
+
+    #[error("Invalid weight curve parameters")]
+    InvalidWeightCurve,
+
+    #[error("Voting in {round_id} has already started")]
+    VotingStarted { round_id: u64 },
+
+    #[error("Score type is longer than {max} bytes")]
+    ScoreTypeTooLong { max: usize },
+
+    #[error("Staking unit must not be zero")]
+    StakingUnitZero,
+
+    #[error("Weight {weight} is lower than the voter's weight {pinned}")]
+    WeightDecreased { pinned: u128, weight: u128 },
+
+    #[error("Cannot retract more than the {contribution} donated to project {project_id}")]
+    RetractExceedsContribution { project_id: u64, contribution: u128 },
+
+    #[error("Donations to project {project_id} were made before retractions were tracked")]
+    RetractNotTracked { project_id: u64 },
 }
@@ -1,6 +1,7 @@
 /// This module contains helper functions for mathematical operations.
 pub mod math {
     use cosmwasm_std::{StdError, StdResult};
+    #[allow(clippy::assign_op_pattern)]
     pub fn log2_u64_with_decimal(x: u64) -> StdResult<u64> {
         if x == 0 {
             return Err(StdError::generic_err("log2(0) is undefined"));
@@ -61,16 +62,209 @@ pub mod math {
     }
 }
 
+/// Fixed-point math, values are returned scaled by `10^precision`.
+pub mod fixed {
+    use cosmwasm_std::{Decimal, Decimal256, Isqrt, StdError, StdResult, Uint128, Uint256};
+
+    /// Precision the contract used before fixed-point math: weights and areas had one decimal.
+    pub const LEGACY_PRECISION: u32 = 1;
+    pub const MAX_PRECISION: u32 = 9;
+    const FRACTION_BITS: u32 = 56;
+
+    fn scale(precision: u32) -> Uint256 {
+        Uint256::from(10u128).pow(precision)
+    }
+
+    /// `floor(sqrt(x) * 10^precision)`
+    pub fn sqrt(x: u128, precision: u32) -> u128 {
+        let root = (Uint256::from(x) * scale(precision * 2)).isqrt();
+        // sqrt(u128::MAX * 10^18) still fits in u128
+        Uint128::try_from(root).unwrap().u128()
+    }
+
+    /// `floor(value * 10^precision)`
+    pub fn from_decimal(value: Decimal, precision: u32) -> u128 {
+        value
+            .atomics()
+            .multiply_ratio(10u128.pow(precision), 10u128.pow(Decimal::DECIMAL_PLACES))
+            .u128()
+    }
+
+    /// `floor(log2(x) * 10^precision)`, the fraction is computed bit by bit by squaring the
+    /// mantissa in `[1, 2)`.
+    pub fn log2(x: u64, precision: u32) -> StdResult<u128> {
+        if x == 0 {
+            return Err(StdError::generic_err("log2(0) is undefined"));
+        }
+        let integer = 63 - x.leading_zeros();
+        let two = Decimal256::from_ratio(2u8, 1u8);
+
+        let mut mantissa = Decimal256::from_ratio(x, 1u64 << integer);
+        let mut bit = Decimal256::one();
+        let mut fraction = Decimal256::zero();
+        for _ in 0..FRACTION_BITS {
+            mantissa = mantissa * mantissa;
+            bit /= two;
+            if mantissa >= two {
+                mantissa /= two;
+                fraction += bit;
+            }
+        }
+
+        let value = (Decimal256::from_ratio(integer, 1u8) + fraction)
+            * Decimal256::from_ratio(scale(precision), 1u8);
+        Uint128::try_from(value.to_uint_floor())
+            .map(|value| value.u128())
+            .map_err(|err| StdError::generic_err(err.to_string()))
+    }
+
+    #[test]
+    fn test_sqrt() {
+        assert_eq!(sqrt(0, 6), 0);
+        assert_eq!(sqrt(10000, 1), 1000);
+        assert_eq!(sqrt(2, 4), 14142);
+        assert_eq!(sqrt(u128::MAX, 9), 18446744073709551615999999999);
+    }
+
+    #[test]
+    fn test_from_decimal() {
+        assert_eq!(from_decimal(Decimal::percent(150), 1), 15);
+        assert_eq!(from_decimal(Decimal::permille(1234), 2), 123);
+        assert_eq!(from_decimal(Decimal::one(), 9), 1_000_000_000);
+    }
+
+    #[test]
+    fn test_log2() {
+        assert!(log2(0, 1).is_err());
+        assert_eq!(log2(1, 6), Ok(0));
+        assert_eq!(log2(3, 5), Ok(158496));
+        assert_eq!(log2(1024, 6), Ok(10_000_000));
+        for x in [2, 3, 25, 1024, 123143400] {
+            assert_eq!(
+                log2(x, LEGACY_PRECISION),
+                super::math::log2_u64_with_decimal(x).map(u128::from)
+            );
+        }
+    }
+}
+
+pub mod hash {
+    use tiny_keccak::{Hasher, Keccak};
+
+    pub fn keccak256(data: &[u8]) -> [u8; 32] {
+        let mut keccak256 = Keccak::v256();
+        let mut hash = [0u8; 32];
+
+        keccak256.update(data);
+        keccak256.finalize(&mut hash);
+        hash
+    }
+
+    #[test]
+    fn test_keccak256() {
+        assert_eq!(
+            hex::encode(keccak256(b"")),
+            "c5d2460186f7233c927e7db2dcc703c0e500b653ca82273b7bfad8045d85a470"
+        );
+    }
+}
+
+/// Merkle proofs for grant claims. Pairs are hashed in sorted order, so proofs
+/// do not need to carry left/right flags. Leaves and inner nodes are hashed with
+/// distinct prefixes, so an inner node can never be claimed as a leaf.
+pub mod merkle {
+    use super::hash::keccak256;
+
+    pub const LEAF_PREFIX: u8 = 0x00;
+    pub const NODE_PREFIX: u8 = 0x01;
+
+    pub fn leaf_hash(index: u64, owner: &str, amount: u128) -> [u8; 32] {
+        let mut msg = vec![LEAF_PREFIX];
+        msg.extend_from_slice(&index.to_le_bytes());
+        msg.extend_from_slice(owner.as_bytes());
+        msg.extend_from_slice(&amount.to_le_bytes());
+        keccak256(&msg)
+    }
+
+    pub fn hash_pair(a: &[u8; 32], b: &[u8; 32]) -> [u8; 32] {
+        let (first, second) = if a <= b { (a, b) } else { (b, a) };
+        let mut msg = Vec::with_capacity(65);
+        msg.push(NODE_PREFIX);
+        msg.extend_from_slice(first);
+        msg.extend_from_slice(second);
+        keccak256(&msg)
+    }
+
+    pub fn verify(root: &[u8; 32], leaf: [u8; 32], proof: &[[u8; 32]]) -> bool {
+        let computed = proof
+            .iter()
+            .fold(leaf, |hash, sibling| hash_pair(&hash, sibling));
+        computed == *root
+    }
+
+    #[test]
+    fn test_verify() {
+        let leaves = [
+            leaf_hash(0, "owner1", 100),
+            leaf_hash(1, "owner2", 200),
+            leaf_hash(2, "owner3", 300),
+        ];
+        let left = hash_pair(&leaves[0], &leaves[1]);
+        let root = hash_pair(&left, &leaves[2]);
+
+        assert!(verify(&root, leaves[0], &[leaves[1], leaves[2]]));
+        assert!(verify(&root, leaves[2], &[left]));
+        assert!(!verify(&root, leaf_hash(2, "owner3", 301), &[left]));
+        assert!(!verify(&root, leaves[1], &[leaves[2]]));
+
+        let mut node = vec![NODE_PREFIX];
+        node.extend_from_slice(leaves[..2].iter().min().unwrap());
+        node.extend_from_slice(leaves[..2].iter().max().unwrap());
+        assert_eq!(left, keccak256(&node));
+        node[0] = LEAF_PREFIX;
+        assert_ne!(left, keccak256(&node));
+    }
+}
+
 pub mod signature {
+    use super::hash::keccak256;
     use cosmwasm_std::{Deps, HexBinary, Uint128};
-    use tiny_keccak::{Hasher, Keccak};
 
+    #[allow(clippy::too_many_arguments, clippy::ptr_arg)]
     pub fn build_msg(
         addr_bytes: &[u8],
         round_id: u64,
         project_ids: &Vec<u64>,
         amounts: &Vec<Uint128>,
-        vcdora: u64,
+        score: u64,
+        timestamp: u64,
+        sig_chain_id: &str,
+        sig_contract_addr: &str,
+    ) -> Vec<u8> {
+        build_attested_msg(
+            addr_bytes,
+            round_id,
+            project_ids,
+            amounts,
+            "",
+            score,
+            timestamp,
+            sig_chain_id,
+            sig_contract_addr,
+        )
+    }
+
+    /// Payload attesting `score` of type `score_type`. The type is length-prefixed in front of
+    /// the score so an attestation cannot be replayed in a round using another score type; an
+    /// empty type keeps the original vcDORA layout.
+    #[allow(clippy::too_many_arguments)]
+    pub fn build_attested_msg(
+        addr_bytes: &[u8],
+        round_id: u64,
+        project_ids: &[u64],
+        amounts: &[Uint128],
+        score_type: &str,
+        score: u64,
         timestamp: u64,
         sig_chain_id: &str,
         sig_contract_addr: &str,
@@ -90,7 +284,11 @@ pub mod signature {
         msg.extend_from_slice(&round_id_bytes);
         msg.extend_from_slice(&project_ids_bytes);
         msg.extend_from_slice(&amounts_bytes);
-        msg.extend_from_slice(&vcdora.to_le_bytes());
+        if !score_type.is_empty() {
+            msg.extend_from_slice(&(score_type.len() as u64).to_le_bytes());
+            msg.extend_from_slice(score_type.as_bytes());
+        }
+        msg.extend_from_slice(&score.to_le_bytes());
         msg.extend_from_slice(&timestamp.to_le_bytes());
         msg.extend_from_slice(sig_chain_id.as_bytes());
         msg.extend_from_slice(sig_contract_addr.as_bytes());
@@ -98,11 +296,7 @@ pub mod signature {
     }
 
     pub fn recover_pubkey(deps: Deps, msg: Vec<u8>, sig: Vec<u8>, recid: u8) -> Vec<u8> {
-        let mut keccak256 = Keccak::v256();
-        let mut hash = [0u8; 32];
-
-        keccak256.update(msg.as_slice());
-        keccak256.finalize(&mut hash);
+        let hash = keccak256(msg.as_slice());
 
         let signature = HexBinary::from(sig);
         deps.api
@@ -123,6 +317,7 @@ pub mod signature {
     }
 
     #[test]
+    #[allow(clippy::needless_borrow)]
     fn test_build_msg() {
         let msg = build_msg(
             &hex::decode("4C87D8f31E3d6EE5969e4002E614a9c72C6A99B8")
@@ -138,4 +333,28 @@ pub mod signature {
         );
         assert_eq!(msg, hex::decode("4c87d8f31e3d6ee5969e4002e614a9c72c6a99b801000000000000000900000000000000080000000000000000008a5d784563010000000000000000000014bbf08ac60200000000000000002a0000000000000044a0476400000000").expect("Decoding failed"));
     }
+
+    #[test]
+    fn test_build_attested_msg() {
+        let addr = hex::decode("4C87D8f31E3d6EE5969e4002E614a9c72C6A99B8").unwrap();
+        let amounts = [Uint128::from(100u128)];
+        let build = |score_type| {
+            build_attested_msg(&addr, 1, &[9], &amounts, score_type, 42, 1682415684, "", "")
+        };
+        assert_eq!(
+            build(""),
+            build_msg(
+                &addr,
+                1,
+                &vec![9],
+                &amounts.to_vec(),
+                42,
+                1682415684,
+                "",
+                ""
+            )
+        );
+        assert_eq!(build("kyc").len(), build("").len() + 8 + 3);
+        assert_ne!(build("kyc"), build("passport"));
+    }
 }
@@ -1,3 +1,5 @@
+// Sylvia's generated message constructors mirror every exec argument, the impl itself re-enables the lint.
+#[allow(clippy::too_many_arguments)]
 pub mod contract;
 pub mod error;
 pub mod helper;
@@ -11,7 +13,7 @@ pub mod entry_point {
 
     use crate::{
         contract::{
-            sv::{ContractExecMsg, ContractQueryMsg, InstantiateMsg},
+            sv::{ContractExecMsg, ContractQueryMsg, InstantiateMsg, MigrateMsg},
             QGContract,
         },
         error::ContractError,
@@ -29,6 +31,11 @@ pub mod entry_point {
         msg.dispatch(&CONTRACT, (deps, env, info))
     }
 
+    #[entry_point]
+    pub fn migrate(deps: DepsMut, env: Env, msg: MigrateMsg) -> Result<Response, ContractError> {
+        msg.dispatch(&CONTRACT, (deps, env))
+    }
+
     #[entry_point]
     pub fn query(deps: Deps, env: Env, msg: ContractQueryMsg) -> Result<Binary, ContractError> {
         msg.dispatch(&CONTRACT, (deps, env))
@@ -1,6 +1,148 @@
 use serde::{Deserialize, Serialize};
 
+use crate::state::{Milestone, Project, ProjectGrant, Proposal, QueuedAction, Role, WeightPolicy};
+
 #[derive(Serialize, Deserialize, Clone, PartialEq, Eq, schemars::JsonSchema, Debug, Default)]
 pub struct AdminListResp {
     pub admins: Vec<String>,
-}
\ No newline at end of file
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, schemars::JsonSchema, Debug, Default)]
+pub struct RoundStatsResp {
+    pub round_id: u64,
+    pub voter_count: u64,
+    pub project_number: u64,
+    pub total_contributions: u128,
+    pub total_area: u128,
+    pub top_by_area: Vec<Project>,
+    pub top_by_contribution: Vec<Project>,
+    pub top_by_votes: Vec<Project>,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, schemars::JsonSchema, Debug, Default)]
+pub struct LeaderboardEntry {
+    pub rank: u32,
+    pub project: Project,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, schemars::JsonSchema, Debug, Default)]
+pub struct LeaderboardResp {
+    pub entries: Vec<LeaderboardEntry>,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, Eq, schemars::JsonSchema, Debug, Default)]
+pub struct VoterEntry {
+    pub address: String,
+    pub amount: u128,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, Eq, schemars::JsonSchema, Debug, Default)]
+pub struct VotersResp {
+    pub voter_count: u64,
+    pub voters: Vec<VoterEntry>,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, schemars::JsonSchema, Debug, Default)]
+pub struct DistributionResp {
+    pub round_id: u64,
+    pub fund: u128,
+    pub eligible_area: u128,
+    pub grants: Vec<ProjectGrant>,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, Eq, schemars::JsonSchema, Debug, Default)]
+pub struct PauseStateResp {
+    pub global: bool,
+    pub round: Option<bool>,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, Eq, schemars::JsonSchema, Debug, Default)]
+pub struct RolesResp {
+    pub is_admin: bool,
+    pub roles: Vec<Role>,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, Eq, schemars::JsonSchema, Debug, Default)]
+pub struct TreasuryResp {
+    pub treasury: String,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, Eq, schemars::JsonSchema, Debug, Default)]
+pub struct FeeCollectorResp {
+    pub fee_collector: String,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, schemars::JsonSchema, Debug, Default)]
+pub struct QueuedActionsResp {
+    pub delay: u64,
+    pub actions: Vec<QueuedAction>,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, schemars::JsonSchema, Debug, Default)]
+pub struct ProposalsResp {
+    pub threshold: u64,
+    pub proposals: Vec<Proposal>,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, schemars::JsonSchema, Debug, Default)]
+pub struct GrantResp {
+    pub grant: ProjectGrant,
+    pub claimed: u128,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, schemars::JsonSchema, Debug, Default)]
+pub struct SnapshotResp {
+    pub round_id: u64,
+    pub finalized_at: u64,
+    pub snapshot_hash: String,
+    pub grants: Vec<ProjectGrant>,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, Eq, schemars::JsonSchema, Debug, Default)]
+pub struct MerkleClaimResp {
+    pub index: u64,
+    pub claimed: bool,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, Eq, schemars::JsonSchema, Debug, Default)]
+pub struct VestingResp {
+    pub round_id: u64,
+    pub project_id: u64,
+    pub matching: u128,
+    pub vested: u128,
+    pub claimed: u128,
+    pub releasable: u128,
+    pub halted_at: Option<u64>,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, schemars::JsonSchema, Debug, Default)]
+pub struct MilestonesResp {
+    pub round_id: u64,
+    pub project_id: u64,
+    pub matching: u128,
+    pub released: u128,
+    pub claimed: u128,
+    pub returned: Option<u128>,
+    pub milestones: Vec<Milestone>,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, schemars::JsonSchema, Debug, Default)]
+pub struct VoterWeightResp {
+    pub round_id: u64,
+    pub voter: String,
+    pub policy: WeightPolicy,
+    pub weight: Option<u128>,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, Eq, schemars::JsonSchema, Debug, Default)]
+pub struct ProjectArea {
+    pub project_id: u64,
+    pub area: u128,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, Eq, schemars::JsonSchema, Debug, Default)]
+pub struct PairwiseAreasResp {
+    pub round_id: u64,
+    pub threshold: u128,
+    pub areas: Vec<ProjectArea>,
+}
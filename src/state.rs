@@ -1,20 +1,105 @@
-use cosmwasm_std::Uint128;
+use std::fmt;
+
+use cosmwasm_std::{Decimal, Uint128};
 use serde::{Deserialize, Serialize};
 use sylvia::schemars;
 
+use crate::helper::fixed::LEGACY_PRECISION;
+
 #[derive(Serialize, Deserialize, Clone, PartialEq, schemars::JsonSchema, Debug, Default)]
 pub enum RoundStatus {
-    #[default] Voting,
+    #[default]
+    Voting,
+    Challenging,
     Finished,
     Withdrawn,
 }
 
 #[derive(Serialize, Deserialize, Clone, PartialEq, schemars::JsonSchema, Debug, Default)]
 pub enum ProjectStatus {
-    #[default] OK,
+    #[default]
+    OK,
     Banned,
 }
 
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, schemars::JsonSchema, Debug, Default)]
+pub enum PayoutMode {
+    #[default]
+    Direct,
+    Merkle,
+    Vested,
+    Milestone,
+}
+
+/// How project areas are turned into matching.
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, schemars::JsonSchema, Debug, Default)]
+pub enum MatchingStrategy {
+    /// Sum of square roots of contributions
+    #[default]
+    Classic,
+    /// Classic, but no project receives more than `cap_bps` of the fund
+    Capped { cap_bps: u64 },
+    /// Pairwise coordination subsidy, each pair of voters is discounted by `threshold / (threshold + k)`
+    /// where `k` is how much the pair already co-funds, in votes. Pairs are formed from raw votes,
+    /// voter weights do not apply
+    Pairwise { threshold: u128 },
+    /// Cluster match, contributions within an uploaded voter cluster are summed before the square root.
+    /// Voter weights do not apply
+    Cluster,
+    /// One token one vote, area grows linearly with weighted votes
+    Linear,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, schemars::JsonSchema, Debug)]
+pub struct WeightStep {
+    pub min_score: u64,
+    pub weight: Decimal,
+}
+
+/// How an attested score (vcDORA) amplifies the votes of a signed ballot. Unsigned ballots weigh 1.0.
+#[derive(Serialize, Deserialize, Clone, PartialEq, schemars::JsonSchema, Debug, Default)]
+pub enum WeightCurve {
+    /// `log2(score + 2)`
+    #[default]
+    Log2,
+    /// `sqrt(score + 1)`
+    Sqrt,
+    /// `min(1 + slope * score, cap)`
+    Linear { slope: Decimal, cap: Decimal },
+    /// Weight of the highest step whose `min_score` the score reaches, 1.0 below the first one
+    Piecewise { steps: Vec<WeightStep> },
+    /// Same weight for every signed ballot
+    Constant { weight: Decimal },
+}
+
+/// Where the score fed into the weight curve comes from.
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, schemars::JsonSchema, Debug, Default)]
+pub enum WeightSource {
+    /// Score attested by the round's signer, unsigned ballots weigh 1.0
+    #[default]
+    Signature,
+    /// Voter's delegated stake in the bonded denom, one score point per `unit`. With `snapshot`,
+    /// stakes uploaded by admins before voting are used instead of live delegations
+    Staking { unit: Uint128, snapshot: bool },
+}
+
+/// Which weight counts when a voter casts several ballots in a round. New rounds pin weights,
+/// rounds stored before the policy existed keep using the latest one.
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, schemars::JsonSchema, Debug, Default)]
+pub enum WeightPolicy {
+    /// Every ballot uses the weight of its own attestation
+    #[default]
+    Latest,
+    /// The first attested weight holds for the rest of the round
+    Pinned,
+    /// Later ballots may raise the weight but never lower it
+    Monotonic,
+}
+
+fn legacy_precision() -> u32 {
+    LEGACY_PRECISION
+}
+
 #[derive(Serialize, Deserialize, Clone, PartialEq, schemars::JsonSchema, Debug, Default)]
 pub struct Round {
     pub id: u64,
@@ -27,6 +112,81 @@ pub struct Round {
     pub total_area: u128,
     pub total_amounts: u128,
     pub pubkey: Vec<u8>,
+    #[serde(default)]
+    pub voter_count: u64,
+    #[serde(default)]
+    pub min_voters: u64,
+    #[serde(default)]
+    pub min_contribution: Uint128,
+    #[serde(default)]
+    pub min_donation: Uint128,
+    #[serde(default)]
+    pub max_donation_per_project: Uint128,
+    #[serde(default)]
+    pub max_donation_per_voter: Uint128,
+    #[serde(default)]
+    pub paused: bool,
+    #[serde(default)]
+    pub withdrawn: u128,
+    #[serde(default)]
+    pub fee_bps: u64,
+    #[serde(default)]
+    pub fees: u128,
+    #[serde(default)]
+    pub fees_claimed: u128,
+    #[serde(default)]
+    pub challenge_period: u64,
+    #[serde(default)]
+    pub challenge_ends_at: u64,
+    #[serde(default)]
+    pub finalized_at: u64,
+    #[serde(default)]
+    pub snapshot_hash: String,
+    #[serde(default)]
+    pub payout_mode: PayoutMode,
+    #[serde(default)]
+    pub merkle_root: String,
+    #[serde(default)]
+    pub merkle_total: u128,
+    /// Matching the frozen distribution allocates to projects
+    #[serde(default)]
+    pub allocated: u128,
+    #[serde(default)]
+    pub merkle_claimed: u128,
+    #[serde(default)]
+    pub claim_deadline: u64,
+    #[serde(default)]
+    pub merkle_swept: bool,
+    #[serde(default)]
+    pub vesting_cliff: u64,
+    #[serde(default)]
+    pub vesting_duration: u64,
+    #[serde(default)]
+    pub rollover_to: u64,
+    #[serde(default)]
+    pub rolled_over: u128,
+    #[serde(default)]
+    pub rolled_in: u128,
+    #[serde(default)]
+    pub matching_strategy: MatchingStrategy,
+    /// Decimals of weights and areas, rounds stored before it was introduced used the legacy one
+    #[serde(default = "legacy_precision")]
+    pub precision: u32,
+    #[serde(default)]
+    pub weight_curve: WeightCurve,
+    /// Kind of score the round's signer attests, e.g. a humanity score or KYC tier; empty means vcDORA
+    #[serde(default)]
+    pub score_type: String,
+    #[serde(default)]
+    pub weight_source: WeightSource,
+    #[serde(default)]
+    pub weight_policy: WeightPolicy,
+    /// Matching deposited so far, including amounts rolled in from earlier rounds
+    #[serde(default)]
+    pub deposited: u128,
+    /// Matching that left the round, through claims, sweeps, halts or rollovers
+    #[serde(default)]
+    pub matching_paid: u128,
 }
 
 #[derive(Serialize, Deserialize, Clone, PartialEq, schemars::JsonSchema, Debug, Default)]
@@ -37,4 +197,121 @@ pub struct Project {
     pub status: ProjectStatus,
     pub votes: u128,
     pub contribution: u128,
+    #[serde(default)]
+    pub voter_count: u64,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, schemars::JsonSchema, Debug, Default)]
+pub struct ProjectGrant {
+    pub project_id: u64,
+    pub owner: String,
+    pub area: u128,
+    pub contribution: u128,
+    pub voter_count: u64,
+    pub eligible: bool,
+    pub matching: u128,
+    pub total: u128,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, schemars::JsonSchema, Debug, Default)]
+pub enum ProjectSort {
+    #[default]
+    Area,
+    Contribution,
+    Votes,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, schemars::JsonSchema, Debug)]
+pub enum Role {
+    RoundManager,
+    ProjectCurator,
+    SignerManager,
+    Treasurer,
+}
+
+impl fmt::Display for Role {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let role = match self {
+            Role::RoundManager => "round_manager",
+            Role::ProjectCurator => "project_curator",
+            Role::SignerManager => "signer_manager",
+            Role::Treasurer => "treasurer",
+        };
+        write!(f, "{}", role)
+    }
+}
+
+/// Sensitive admin actions that go through the timelock when it is enabled.
+#[derive(Serialize, Deserialize, Clone, PartialEq, schemars::JsonSchema, Debug)]
+pub enum AdminAction {
+    SetPubkey {
+        round_id: u64,
+        pubkey: Vec<u8>,
+    },
+    DelAdmin {
+        admin: String,
+    },
+    Withdraw {
+        round_id: u64,
+        amount: Option<Uint128>,
+    },
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, schemars::JsonSchema, Debug)]
+pub struct QueuedAction {
+    pub id: u64,
+    pub action: AdminAction,
+    pub proposer: String,
+    pub eta: u64,
+}
+
+/// Actions that need M-of-N admin approvals once an approval threshold is configured.
+#[derive(Serialize, Deserialize, Clone, PartialEq, schemars::JsonSchema, Debug)]
+pub enum ProposalAction {
+    EndRound {
+        round_id: u64,
+    },
+    Withdraw {
+        round_id: u64,
+        amount: Option<Uint128>,
+    },
+    SweepUnclaimed {
+        round_id: u64,
+    },
+    CloseMilestones {
+        round_id: u64,
+        project_id: u64,
+        next_round_id: Option<u64>,
+    },
+    HaltVesting {
+        round_id: u64,
+        project_id: u64,
+    },
+    Rollover {
+        round_id: u64,
+        next_round_id: u64,
+    },
+    SetMerkleRoot {
+        round_id: u64,
+        merkle_root: String,
+        total_amount: Uint128,
+        claim_deadline: u64,
+    },
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, schemars::JsonSchema, Debug)]
+pub struct Proposal {
+    pub id: u64,
+    pub action: ProposalAction,
+    pub proposer: String,
+    pub approvals: Vec<String>,
+    pub executed: bool,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, schemars::JsonSchema, Debug)]
+pub struct Milestone {
+    pub id: u64,
+    pub description: String,
+    pub percentage: u64,
+    pub completed: bool,
 }
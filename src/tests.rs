@@ -1,13 +1,145 @@
 #[cfg(test)]
+#[allow(clippy::module_inception)]
 mod tests {
     use crate::contract::sv::{
-        ContractExecMsg, ContractQueryMsg, ExecMsg, InstantiateMsg, QueryMsg,
+        ContractExecMsg, ContractQueryMsg, ExecMsg, InstantiateMsg, MigrateMsg, QueryMsg,
     };
-    use crate::entry_point::{execute, instantiate, query};
-    use crate::responses::AdminListResp;
-    use crate::state::{Project, ProjectStatus, Round, RoundStatus};
-    use cosmwasm_std::testing::{mock_dependencies, mock_env, mock_info};
-    use cosmwasm_std::{from_json, Coin, DenomMetadata, DenomUnit, Timestamp, Uint128};
+    use crate::contract::QGContract;
+    use crate::entry_point::{execute, instantiate, migrate, query};
+    use crate::error::ContractError;
+    use crate::helper::hash::keccak256;
+    use crate::helper::merkle;
+    use crate::responses::{
+        AdminListResp, DistributionResp, FeeCollectorResp, GrantResp, LeaderboardResp,
+        MerkleClaimResp, MilestonesResp, PairwiseAreasResp, PauseStateResp, ProposalsResp,
+        QueuedActionsResp, RolesResp, RoundStatsResp, SnapshotResp, VestingResp, VoterEntry,
+        VoterWeightResp, VotersResp,
+    };
+    use crate::state::{
+        AdminAction, MatchingStrategy, PayoutMode, Project, ProjectGrant, ProjectSort,
+        ProjectStatus, ProposalAction, Role, Round, RoundStatus, WeightCurve, WeightPolicy,
+        WeightSource, WeightStep,
+    };
+    use cosmwasm_std::testing::{
+        mock_dependencies, mock_env, mock_info, MockApi, MockQuerier, MockStorage,
+    };
+    use cosmwasm_std::{
+        coins, from_json, to_json_vec, Addr, BankMsg, Coin, CosmosMsg, Decimal, DenomMetadata,
+        DenomUnit, Env, FullDelegation, OwnedDeps, Response, Storage, Timestamp, Uint128,
+    };
+
+    type MockDeps = OwnedDeps<MockStorage, MockApi, MockQuerier>;
+
+    /// Instantiates the contract with `admin1` and starts round 1 with `projects` projects.
+    fn setup_round(projects: u64) -> (MockDeps, Env) {
+        setup_round_with(projects, None)
+    }
+
+    /// Like `setup_round`, with round 1 using `matching_strategy`.
+    fn setup_round_with(
+        projects: u64,
+        matching_strategy: Option<MatchingStrategy>,
+    ) -> (MockDeps, Env) {
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+
+        deps.querier.update_balance(
+            "test",
+            vec![Coin {
+                denom: "inj".to_string(),
+                amount: Uint128::from(1000000000000000000u128),
+            }],
+        );
+
+        instantiate(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("owner", &[]),
+            InstantiateMsg {
+                admins: vec!["admin1".to_owned()],
+            },
+        )
+        .unwrap();
+
+        let msg = ExecMsg::StartRound {
+            tax_adjustment_multiplier: 5,
+            donation_denom: "inj".to_string(),
+            voting_unit: Uint128::from(1_000_000_000_000_000_000u128),
+            fund: Uint128::from(4000u128),
+            pubkey: vec![],
+            matching_strategy,
+        };
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("admin1", &coins(4000, "inj")),
+            ContractExecMsg::QGContract(msg),
+        )
+        .unwrap();
+
+        let msg = ExecMsg::BatchUploadProject {
+            round_id: 1,
+            owner_addresses: (1..=projects).map(|id| format!("owner{}", id)).collect(),
+        };
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("admin1", &[]),
+            ContractExecMsg::QGContract(msg),
+        )
+        .unwrap();
+
+        (deps, env)
+    }
+
+    /// Casts an unsigned vote from `voter` in round 1, paying exactly the summed amounts.
+    fn vote(
+        deps: &mut MockDeps,
+        env: &Env,
+        voter: &str,
+        project_ids: Vec<u64>,
+        amounts: Vec<u128>,
+    ) -> Result<Response, ContractError> {
+        let total: u128 = amounts.iter().sum();
+        let msg = ExecMsg::WeightedBatchVote {
+            round_id: 1,
+            project_ids,
+            amounts: amounts.into_iter().map(Uint128::from).collect(),
+            score: 0,
+            recid: 0,
+            sig: vec![],
+            timestamp: 0,
+            sig_chain_id: "".to_string(),
+            sig_contract_addr: "".to_string(),
+        };
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info(
+                voter,
+                &[Coin {
+                    denom: "inj".to_string(),
+                    amount: Uint128::from(total),
+                }],
+            ),
+            ContractExecMsg::QGContract(msg),
+        )
+    }
+
+    /// Executes `msg` as `sender` with no funds attached.
+    fn exec(
+        deps: &mut MockDeps,
+        env: &Env,
+        sender: &str,
+        msg: ExecMsg,
+    ) -> Result<Response, ContractError> {
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info(sender, &[]),
+            ContractExecMsg::QGContract(msg),
+        )
+    }
 
     #[test]
     fn admin_list_query() {
@@ -78,6 +210,7 @@ mod tests {
     }
 
     #[test]
+    #[allow(clippy::useless_vec)]
     fn test_all() {
         let mut deps = mock_dependencies();
         let mut env = mock_env();
@@ -119,13 +252,14 @@ mod tests {
         .unwrap();
 
         // Start round
-        let info = mock_info("admin1", &[]);
+        let info = mock_info("admin1", &coins(4000, "inj"));
         let msg = ExecMsg::StartRound {
             tax_adjustment_multiplier: 5,
             donation_denom: "inj".to_string(),
             voting_unit: Uint128::from(1_000_000_000_000_000_000u128),
             fund: Uint128::from(4000u128),
             pubkey: vec![],
+            matching_strategy: None,
         };
         execute(
             deps.as_mut(),
@@ -157,6 +291,41 @@ mod tests {
                 total_area: 0,
                 total_amounts: 0,
                 pubkey: vec![],
+                voter_count: 0,
+                min_voters: 0,
+                min_contribution: Uint128::zero(),
+                min_donation: Uint128::zero(),
+                max_donation_per_project: Uint128::zero(),
+                max_donation_per_voter: Uint128::zero(),
+                paused: false,
+                withdrawn: 0,
+                fee_bps: 0,
+                fees: 0,
+                fees_claimed: 0,
+                challenge_period: 0,
+                challenge_ends_at: 0,
+                finalized_at: 0,
+                snapshot_hash: String::new(),
+                payout_mode: PayoutMode::Direct,
+                merkle_root: String::new(),
+                merkle_total: 0,
+                allocated: 0,
+                merkle_claimed: 0,
+                claim_deadline: 0,
+                merkle_swept: false,
+                vesting_cliff: 0,
+                vesting_duration: 0,
+                rollover_to: 0,
+                rolled_over: 0,
+                rolled_in: 0,
+                matching_strategy: MatchingStrategy::Classic,
+                precision: 1,
+                weight_curve: WeightCurve::Log2,
+                score_type: String::new(),
+                weight_source: WeightSource::Signature,
+                weight_policy: WeightPolicy::Pinned,
+                deposited: 4000,
+                matching_paid: 0,
             }
         );
 
@@ -194,6 +363,41 @@ mod tests {
                 total_area: 0,
                 total_amounts: 0,
                 pubkey: vec![],
+                voter_count: 0,
+                min_voters: 0,
+                min_contribution: Uint128::zero(),
+                min_donation: Uint128::zero(),
+                max_donation_per_project: Uint128::zero(),
+                max_donation_per_voter: Uint128::zero(),
+                paused: false,
+                withdrawn: 0,
+                fee_bps: 0,
+                fees: 0,
+                fees_claimed: 0,
+                challenge_period: 0,
+                challenge_ends_at: 0,
+                finalized_at: 0,
+                snapshot_hash: String::new(),
+                payout_mode: PayoutMode::Direct,
+                merkle_root: String::new(),
+                merkle_total: 0,
+                allocated: 0,
+                merkle_claimed: 0,
+                claim_deadline: 0,
+                merkle_swept: false,
+                vesting_cliff: 0,
+                vesting_duration: 0,
+                rollover_to: 0,
+                rolled_over: 0,
+                rolled_in: 0,
+                matching_strategy: MatchingStrategy::Classic,
+                precision: 1,
+                weight_curve: WeightCurve::Log2,
+                score_type: String::new(),
+                weight_source: WeightSource::Signature,
+                weight_policy: WeightPolicy::Pinned,
+                deposited: 4000,
+                matching_paid: 0,
             }
         );
         let resp = query(
@@ -215,6 +419,7 @@ mod tests {
                 status: ProjectStatus::OK,
                 votes: 0,
                 contribution: 0,
+                voter_count: 0,
             }
         );
 
@@ -223,7 +428,7 @@ mod tests {
             round_id: 1,
             project_ids: vec![1],
             amounts: vec![Uint128::from(160000u128)],
-            vcdora: 0,
+            score: 0,
             recid: 0,
             sig: vec![],
             timestamp: 0,
@@ -249,7 +454,7 @@ mod tests {
             round_id: 1,
             project_ids: vec![1],
             amounts: vec![Uint128::from(90000u128)],
-            vcdora: 1,
+            score: 1,
             recid: 0,
             sig: vec![],
             timestamp: 0,
@@ -275,7 +480,7 @@ mod tests {
             round_id: 1,
             project_ids: vec![2],
             amounts: vec![Uint128::from(160000u128)],
-            vcdora: 123143400,
+            score: 123143400,
             recid: 0,
             sig: vec![],
             timestamp: 0,
@@ -317,6 +522,41 @@ mod tests {
                 total_area: 500 * 10 + 400 * 10,
                 total_amounts: 410000,
                 pubkey: vec![],
+                voter_count: 1,
+                min_voters: 0,
+                min_contribution: Uint128::zero(),
+                min_donation: Uint128::zero(),
+                max_donation_per_project: Uint128::zero(),
+                max_donation_per_voter: Uint128::zero(),
+                paused: false,
+                withdrawn: 0,
+                fee_bps: 0,
+                fees: 0,
+                fees_claimed: 0,
+                challenge_period: 0,
+                challenge_ends_at: 0,
+                finalized_at: 0,
+                snapshot_hash: String::new(),
+                payout_mode: PayoutMode::Direct,
+                merkle_root: String::new(),
+                merkle_total: 0,
+                allocated: 0,
+                merkle_claimed: 0,
+                claim_deadline: 0,
+                merkle_swept: false,
+                vesting_cliff: 0,
+                vesting_duration: 0,
+                rollover_to: 0,
+                rolled_over: 0,
+                rolled_in: 0,
+                matching_strategy: MatchingStrategy::Classic,
+                precision: 1,
+                weight_curve: WeightCurve::Log2,
+                score_type: String::new(),
+                weight_source: WeightSource::Signature,
+                weight_policy: WeightPolicy::Pinned,
+                deposited: 4000,
+                matching_paid: 0,
             }
         );
         let resp = query(
@@ -338,6 +578,7 @@ mod tests {
                 status: ProjectStatus::OK,
                 votes: 160000,
                 contribution: 160000,
+                voter_count: 1,
             }
         );
 
@@ -368,6 +609,28 @@ mod tests {
         .unwrap();
 
         // Check round status
+        let grants = vec![
+            ProjectGrant {
+                project_id: 1,
+                owner: "1".to_string(),
+                area: 5000,
+                contribution: 250000,
+                voter_count: 1,
+                eligible: true,
+                matching: 2222,
+                total: 252222,
+            },
+            ProjectGrant {
+                project_id: 2,
+                owner: "2".to_string(),
+                area: 4000,
+                contribution: 160000,
+                voter_count: 1,
+                eligible: true,
+                matching: 1777,
+                total: 161777,
+            },
+        ];
         let resp = query(
             deps.as_ref(),
             env.clone(),
@@ -388,12 +651,50 @@ mod tests {
                 total_area: 500 * 10 + 400 * 10,
                 total_amounts: 410000,
                 pubkey,
+                voter_count: 1,
+                min_voters: 0,
+                min_contribution: Uint128::zero(),
+                min_donation: Uint128::zero(),
+                max_donation_per_project: Uint128::zero(),
+                max_donation_per_voter: Uint128::zero(),
+                paused: false,
+                withdrawn: 0,
+                fee_bps: 0,
+                fees: 0,
+                fees_claimed: 0,
+                challenge_period: 0,
+                challenge_ends_at: 0,
+                finalized_at: 1682415684,
+                snapshot_hash: hex::encode(keccak256(&to_json_vec(&grants).unwrap())),
+                payout_mode: PayoutMode::Direct,
+                merkle_root: String::new(),
+                merkle_total: 0,
+                allocated: 3999,
+                merkle_claimed: 0,
+                claim_deadline: 0,
+                merkle_swept: false,
+                vesting_cliff: 0,
+                vesting_duration: 0,
+                rollover_to: 0,
+                rolled_over: 0,
+                rolled_in: 0,
+                matching_strategy: MatchingStrategy::Classic,
+                precision: 1,
+                weight_curve: WeightCurve::Log2,
+                score_type: String::new(),
+                weight_source: WeightSource::Signature,
+                weight_policy: WeightPolicy::Pinned,
+                deposited: 4000,
+                matching_paid: 0,
             }
         );
 
         // Withdraw
         let info = mock_info("admin1", &[]);
-        let msg = ExecMsg::Withdraw { round_id: 1 };
+        let msg = ExecMsg::Withdraw {
+            round_id: 1,
+            amount: None,
+        };
         execute(
             deps.as_mut(),
             env.clone(),
@@ -402,4 +703,2432 @@ mod tests {
         )
         .unwrap();
     }
+
+    #[test]
+    fn round_stats_and_leaderboard() {
+        let (mut deps, env) = setup_round(3);
+
+        vote(&mut deps, &env, "user1", vec![1, 2], vec![10000, 40000]).unwrap();
+        vote(&mut deps, &env, "user2", vec![2], vec![10000]).unwrap();
+        vote(&mut deps, &env, "user1", vec![3], vec![90000]).unwrap();
+
+        let msg = QueryMsg::RoundStats {
+            round_id: 1,
+            top_n: Some(2),
+        };
+        let resp = query(
+            deps.as_ref(),
+            env.clone(),
+            ContractQueryMsg::QGContract(msg),
+        )
+        .unwrap();
+        let resp: RoundStatsResp = from_json(&resp).unwrap();
+        assert_eq!(resp.voter_count, 2);
+        assert_eq!(resp.project_number, 3);
+        assert_eq!(resp.total_contributions, 150000);
+        assert_eq!(resp.total_area, 1000 + 2000 + 1000 + 3000);
+        let ids = |projects: &Vec<Project>| projects.iter().map(|p| p.id).collect::<Vec<_>>();
+        assert_eq!(ids(&resp.top_by_area), vec![2, 3]);
+        assert_eq!(ids(&resp.top_by_contribution), vec![3, 2]);
+        assert_eq!(ids(&resp.top_by_votes), vec![3, 2]);
+
+        let msg = QueryMsg::Leaderboard {
+            round_id: 1,
+            sort_by: Some(ProjectSort::Area),
+            start_after: Some(1),
+            limit: Some(5),
+        };
+        let resp = query(deps.as_ref(), env, ContractQueryMsg::QGContract(msg)).unwrap();
+        let resp: LeaderboardResp = from_json(&resp).unwrap();
+        let ranks = resp
+            .entries
+            .iter()
+            .map(|entry| (entry.rank, entry.project.id))
+            .collect::<Vec<_>>();
+        assert_eq!(ranks, vec![(2, 3), (3, 1)]);
+    }
+
+    #[test]
+    fn unique_voters_and_min_voters_eligibility() {
+        let (mut deps, env) = setup_round(2);
+
+        vote(&mut deps, &env, "user1", vec![1, 2], vec![10000, 40000]).unwrap();
+        vote(&mut deps, &env, "user1", vec![1], vec![30000]).unwrap();
+        vote(&mut deps, &env, "user2", vec![1], vec![10000]).unwrap();
+
+        let msg = QueryMsg::ProjectVoters {
+            round_id: 1,
+            project_id: 1,
+            start_after: None,
+            limit: None,
+        };
+        let resp = query(
+            deps.as_ref(),
+            env.clone(),
+            ContractQueryMsg::QGContract(msg),
+        )
+        .unwrap();
+        let resp: VotersResp = from_json(&resp).unwrap();
+        assert_eq!(
+            resp,
+            VotersResp {
+                voter_count: 2,
+                voters: vec![
+                    VoterEntry {
+                        address: "user1".to_string(),
+                        amount: 40000,
+                    },
+                    VoterEntry {
+                        address: "user2".to_string(),
+                        amount: 10000,
+                    },
+                ],
+            }
+        );
+
+        let msg = QueryMsg::RoundVoters {
+            round_id: 1,
+            start_after: Some("user1".to_string()),
+            limit: None,
+        };
+        let resp = query(
+            deps.as_ref(),
+            env.clone(),
+            ContractQueryMsg::QGContract(msg),
+        )
+        .unwrap();
+        let resp: VotersResp = from_json(&resp).unwrap();
+        assert_eq!(resp.voter_count, 2);
+        assert_eq!(
+            resp.voters,
+            vec![VoterEntry {
+                address: "user2".to_string(),
+                amount: 10000,
+            }]
+        );
+
+        // Project 2 has a single supporter and drops out of matching
+        let msg = ExecMsg::SetEligibility {
+            round_id: 1,
+            min_voters: 2,
+            min_contribution: Uint128::zero(),
+        };
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("admin1", &[]),
+            ContractExecMsg::QGContract(msg),
+        )
+        .unwrap();
+
+        let msg = QueryMsg::Distribution { round_id: 1 };
+        let resp = query(deps.as_ref(), env, ContractQueryMsg::QGContract(msg)).unwrap();
+        let resp: DistributionResp = from_json(&resp).unwrap();
+        assert_eq!(resp.eligible_area, 2000 + 1000);
+        let grants = resp
+            .grants
+            .iter()
+            .map(|grant| {
+                (
+                    grant.project_id,
+                    grant.eligible,
+                    grant.matching,
+                    grant.total,
+                )
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            grants,
+            vec![(1, true, 4000, 50000 + 4000), (2, false, 0, 40000)]
+        );
+    }
+
+    #[test]
+    fn min_contribution_eligibility() {
+        let (mut deps, env) = setup_round(3);
+
+        vote(
+            &mut deps,
+            &env,
+            "user1",
+            vec![1, 2, 3],
+            vec![10000, 40000, 90000],
+        )
+        .unwrap();
+        vote(&mut deps, &env, "user2", vec![1], vec![30000]).unwrap();
+
+        let msg = ExecMsg::SetEligibility {
+            round_id: 1,
+            min_voters: 1,
+            min_contribution: Uint128::from(40000u128),
+        };
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("admin1", &[]),
+            ContractExecMsg::QGContract(msg),
+        )
+        .unwrap();
+
+        let msg = QueryMsg::Distribution { round_id: 1 };
+        let resp = query(
+            deps.as_ref(),
+            env.clone(),
+            ContractQueryMsg::QGContract(msg),
+        )
+        .unwrap();
+        let resp: DistributionResp = from_json(&resp).unwrap();
+        // Every project reaches 40000
+        assert!(resp.grants.iter().all(|grant| grant.eligible));
+        assert_eq!(resp.eligible_area, 1000 + 1732 + 2000 + 3000);
+
+        let msg = ExecMsg::SetEligibility {
+            round_id: 1,
+            min_voters: 1,
+            min_contribution: Uint128::from(50000u128),
+        };
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("admin1", &[]),
+            ContractExecMsg::QGContract(msg),
+        )
+        .unwrap();
+
+        let msg = QueryMsg::Distribution { round_id: 1 };
+        let resp = query(deps.as_ref(), env, ContractQueryMsg::QGContract(msg)).unwrap();
+        let resp: DistributionResp = from_json(&resp).unwrap();
+        let grants = resp
+            .grants
+            .iter()
+            .map(|grant| {
+                (
+                    grant.project_id,
+                    grant.eligible,
+                    grant.matching,
+                    grant.total,
+                )
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            grants,
+            vec![
+                (1, false, 0, 40000),
+                (2, false, 0, 40000),
+                (3, true, 4000, 90000 + 4000),
+            ]
+        );
+    }
+
+    #[test]
+    fn donation_limits() {
+        let (mut deps, env) = setup_round(2);
+
+        let msg = ExecMsg::SetDonationLimits {
+            round_id: 1,
+            min_donation: Uint128::from(10000u128),
+            max_donation_per_project: Uint128::from(50000u128),
+            max_donation_per_voter: Uint128::from(80000u128),
+        };
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("admin1", &[]),
+            ContractExecMsg::QGContract(msg),
+        )
+        .unwrap();
+
+        let err = vote(&mut deps, &env, "user1", vec![1], vec![9999]).unwrap_err();
+        assert_eq!(
+            err,
+            ContractError::DonationBelowMinimum {
+                amount: 9999,
+                min: 10000,
+            }
+        );
+
+        vote(&mut deps, &env, "user1", vec![1], vec![40000]).unwrap();
+        let err = vote(&mut deps, &env, "user1", vec![1], vec![20000]).unwrap_err();
+        assert_eq!(
+            err,
+            ContractError::ProjectDonationCapExceeded {
+                project_id: 1,
+                cap: 50000,
+            }
+        );
+
+        // The per-project cap is per voter
+        vote(&mut deps, &env, "user2", vec![1], vec![50000]).unwrap();
+
+        vote(&mut deps, &env, "user1", vec![1, 2], vec![10000, 20000]).unwrap();
+        let err = vote(&mut deps, &env, "user1", vec![2], vec![20000]).unwrap_err();
+        assert_eq!(err, ContractError::VoterDonationCapExceeded { cap: 80000 });
+    }
+
+    #[test]
+    fn pause_and_unpause() {
+        let (mut deps, env) = setup_round(1);
+
+        // Only the owner can pause
+        let msg = ExecMsg::Pause { round_id: None };
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("admin1", &[]),
+            ContractExecMsg::QGContract(msg.clone()),
+        )
+        .unwrap_err();
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("owner", &[]),
+            ContractExecMsg::QGContract(msg),
+        )
+        .unwrap();
+
+        let err = vote(&mut deps, &env, "user1", vec![1], vec![10000]).unwrap_err();
+        assert_eq!(err, ContractError::Paused);
+
+        let msg = ExecMsg::Unpause { round_id: None };
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("owner", &[]),
+            ContractExecMsg::QGContract(msg),
+        )
+        .unwrap();
+        let msg = ExecMsg::Pause { round_id: Some(1) };
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("owner", &[]),
+            ContractExecMsg::QGContract(msg),
+        )
+        .unwrap();
+
+        let msg = QueryMsg::PauseState { round_id: Some(1) };
+        let resp = query(
+            deps.as_ref(),
+            env.clone(),
+            ContractQueryMsg::QGContract(msg),
+        )
+        .unwrap();
+        let resp: PauseStateResp = from_json(&resp).unwrap();
+        assert_eq!(
+            resp,
+            PauseStateResp {
+                global: false,
+                round: Some(true),
+            }
+        );
+
+        let err = vote(&mut deps, &env, "user1", vec![1], vec![10000]).unwrap_err();
+        assert_eq!(err, ContractError::RoundPaused { round_id: 1 });
+
+        let msg = ExecMsg::Unpause { round_id: Some(1) };
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("owner", &[]),
+            ContractExecMsg::QGContract(msg),
+        )
+        .unwrap();
+        vote(&mut deps, &env, "user1", vec![1], vec![10000]).unwrap();
+    }
+
+    #[test]
+    fn role_based_permissions() {
+        let (mut deps, env) = setup_round(1);
+
+        let msg = ExecMsg::GrantRole {
+            address: "curator".to_string(),
+            role: Role::ProjectCurator,
+        };
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("admin1", &[]),
+            ContractExecMsg::QGContract(msg.clone()),
+        )
+        .unwrap_err();
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("owner", &[]),
+            ContractExecMsg::QGContract(msg),
+        )
+        .unwrap();
+
+        let msg = QueryMsg::RolesOf {
+            address: "curator".to_string(),
+        };
+        let resp = query(
+            deps.as_ref(),
+            env.clone(),
+            ContractQueryMsg::QGContract(msg),
+        )
+        .unwrap();
+        let resp: RolesResp = from_json(&resp).unwrap();
+        assert_eq!(
+            resp,
+            RolesResp {
+                is_admin: false,
+                roles: vec![Role::ProjectCurator],
+            }
+        );
+
+        let msg = ExecMsg::BatchUploadProject {
+            round_id: 1,
+            owner_addresses: vec!["owner2".to_string()],
+        };
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("curator", &[]),
+            ContractExecMsg::QGContract(msg.clone()),
+        )
+        .unwrap();
+
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("curator", &[]),
+            ContractExecMsg::QGContract(ExecMsg::EndRound { round_id: 1 }),
+        )
+        .unwrap_err();
+        assert_eq!(
+            err,
+            ContractError::MissingRole {
+                sender: Addr::unchecked("curator"),
+                role: Role::RoundManager,
+            }
+        );
+
+        let revoke = ExecMsg::RevokeRole {
+            address: "curator".to_string(),
+            role: Role::ProjectCurator,
+        };
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("owner", &[]),
+            ContractExecMsg::QGContract(revoke),
+        )
+        .unwrap();
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("curator", &[]),
+            ContractExecMsg::QGContract(msg),
+        )
+        .unwrap_err();
+
+        // Being an admin does not grant any role by itself
+        let msg = ExecMsg::AddAdmin {
+            admin: "admin9".to_string(),
+        };
+        exec(&mut deps, &env, "owner", msg).unwrap();
+        let err = exec(&mut deps, &env, "admin9", ExecMsg::EndRound { round_id: 1 }).unwrap_err();
+        assert_eq!(
+            err,
+            ContractError::MissingRole {
+                sender: Addr::unchecked("admin9"),
+                role: Role::RoundManager,
+            }
+        );
+
+        let roles_of = |deps: &MockDeps, address: &str| -> RolesResp {
+            let msg = QueryMsg::RolesOf {
+                address: address.to_string(),
+            };
+            let resp = query(deps.as_ref(), mock_env(), ContractQueryMsg::QGContract(msg)).unwrap();
+            from_json(&resp).unwrap()
+        };
+        let all_roles = vec![
+            Role::RoundManager,
+            Role::ProjectCurator,
+            Role::SignerManager,
+            Role::Treasurer,
+        ];
+        assert_eq!(roles_of(&deps, "admin1").roles, all_roles);
+        for role in all_roles.clone() {
+            let msg = ExecMsg::RevokeRole {
+                address: "admin1".to_string(),
+                role,
+            };
+            exec(&mut deps, &env, "owner", msg).unwrap();
+        }
+        assert_eq!(roles_of(&deps, "admin1").roles, vec![]);
+        migrate(deps.as_mut(), env.clone(), MigrateMsg { precision: None }).unwrap();
+        assert_eq!(roles_of(&deps, "admin1").roles, vec![]);
+
+        // Removing an admin also drops their roles
+        let msg = ExecMsg::GrantRole {
+            address: "admin9".to_string(),
+            role: Role::Treasurer,
+        };
+        exec(&mut deps, &env, "owner", msg).unwrap();
+        let msg = ExecMsg::DelAdmin {
+            admin: "admin9".to_string(),
+        };
+        exec(&mut deps, &env, "owner", msg).unwrap();
+        assert_eq!(roles_of(&deps, "admin9").roles, vec![]);
+
+        // Migrating a deployment from before versioning grants every role to its admins
+        deps.storage.remove(b"contract_info");
+        migrate(deps.as_mut(), env, MigrateMsg { precision: None }).unwrap();
+        assert_eq!(roles_of(&deps, "admin1").roles, all_roles);
+        assert_eq!(roles_of(&deps, "admin9").roles, vec![]);
+    }
+
+    #[test]
+    fn withdraw_to_treasury() {
+        let (mut deps, env) = setup_round(1);
+
+        vote(&mut deps, &env, "user1", vec![1], vec![250000]).unwrap();
+        exec(
+            &mut deps,
+            &env,
+            "owner",
+            ExecMsg::SetTreasury {
+                treasury: "treasury".to_string(),
+            },
+        )
+        .unwrap();
+        exec(&mut deps, &env, "admin1", ExecMsg::EndRound { round_id: 1 }).unwrap();
+
+        let resp = exec(
+            &mut deps,
+            &env,
+            "admin1",
+            ExecMsg::Withdraw {
+                round_id: 1,
+                amount: Some(Uint128::from(100000u128)),
+            },
+        )
+        .unwrap();
+        assert_eq!(
+            resp.messages[0].msg,
+            BankMsg::Send {
+                to_address: "treasury".to_string(),
+                amount: coins(100000, "inj"),
+            }
+            .into()
+        );
+
+        let err = exec(
+            &mut deps,
+            &env,
+            "admin1",
+            ExecMsg::Withdraw {
+                round_id: 1,
+                amount: Some(Uint128::from(200000u128)),
+            },
+        )
+        .unwrap_err();
+        assert_eq!(
+            err,
+            ContractError::WithdrawExceedsAvailable {
+                available: 150000,
+                requested: 200000,
+            }
+        );
+
+        exec(
+            &mut deps,
+            &env,
+            "admin1",
+            ExecMsg::Withdraw {
+                round_id: 1,
+                amount: None,
+            },
+        )
+        .unwrap();
+        let resp = query(
+            deps.as_ref(),
+            env,
+            ContractQueryMsg::QGContract(QueryMsg::Round { round_id: 1 }),
+        )
+        .unwrap();
+        let round: Round = from_json(&resp).unwrap();
+        assert_eq!(round.withdrawn, 250000);
+        assert_eq!(round.status, RoundStatus::Withdrawn);
+    }
+
+    #[test]
+    fn platform_fee() {
+        let (mut deps, env) = setup_round(1);
+
+        let err = exec(
+            &mut deps,
+            &env,
+            "admin1",
+            ExecMsg::SetFee {
+                round_id: 1,
+                fee_bps: 10001,
+            },
+        )
+        .unwrap_err();
+        assert_eq!(err, ContractError::InvalidFeeBps { fee_bps: 10001 });
+        exec(
+            &mut deps,
+            &env,
+            "admin1",
+            ExecMsg::SetFee {
+                round_id: 1,
+                fee_bps: 250,
+            },
+        )
+        .unwrap();
+        exec(
+            &mut deps,
+            &env,
+            "owner",
+            ExecMsg::SetFeeCollector {
+                fee_collector: "collector".to_string(),
+            },
+        )
+        .unwrap();
+        let resp = query(
+            deps.as_ref(),
+            env.clone(),
+            ContractQueryMsg::QGContract(QueryMsg::FeeCollector {}),
+        )
+        .unwrap();
+        let resp: FeeCollectorResp = from_json(&resp).unwrap();
+        assert_eq!(resp.fee_collector, "collector");
+
+        vote(&mut deps, &env, "user1", vec![1], vec![40000]).unwrap();
+
+        let resp = query(
+            deps.as_ref(),
+            env.clone(),
+            ContractQueryMsg::QGContract(QueryMsg::Project {
+                round_id: 1,
+                project_id: 1,
+            }),
+        )
+        .unwrap();
+        let project: Project = from_json(&resp).unwrap();
+        assert_eq!(project.contribution, 39000);
+        assert_eq!(project.votes, 40000);
+
+        exec(
+            &mut deps,
+            &env,
+            "admin1",
+            ExecMsg::ClaimFees { round_id: 1 },
+        )
+        .unwrap_err();
+        let resp = exec(
+            &mut deps,
+            &env,
+            "collector",
+            ExecMsg::ClaimFees { round_id: 1 },
+        )
+        .unwrap();
+        assert_eq!(
+            resp.messages[0].msg,
+            BankMsg::Send {
+                to_address: "collector".to_string(),
+                amount: coins(1000, "inj"),
+            }
+            .into()
+        );
+
+        let resp = query(
+            deps.as_ref(),
+            env,
+            ContractQueryMsg::QGContract(QueryMsg::Round { round_id: 1 }),
+        )
+        .unwrap();
+        let round: Round = from_json(&resp).unwrap();
+        assert_eq!(round.total_amounts, 39000);
+        assert_eq!((round.fees, round.fees_claimed), (1000, 1000));
+    }
+
+    #[test]
+    fn timelocked_admin_actions() {
+        let (mut deps, mut env) = setup_round(1);
+
+        vote(&mut deps, &env, "user1", vec![1], vec![10000]).unwrap();
+        exec(&mut deps, &env, "admin1", ExecMsg::EndRound { round_id: 1 }).unwrap();
+        exec(
+            &mut deps,
+            &env,
+            "owner",
+            ExecMsg::SetTimelock { delay: 3600 },
+        )
+        .unwrap();
+
+        let withdraw = ExecMsg::Withdraw {
+            round_id: 1,
+            amount: None,
+        };
+        let resp = exec(&mut deps, &env, "admin1", withdraw).unwrap();
+        assert!(resp.messages.is_empty());
+        let del_admin = ExecMsg::DelAdmin {
+            admin: "admin1".to_string(),
+        };
+        exec(&mut deps, &env, "owner", del_admin).unwrap();
+
+        let msg = QueryMsg::QueuedActions {
+            start_after: None,
+            limit: None,
+        };
+        let resp = query(
+            deps.as_ref(),
+            env.clone(),
+            ContractQueryMsg::QGContract(msg),
+        )
+        .unwrap();
+        let resp: QueuedActionsResp = from_json(&resp).unwrap();
+        assert_eq!(resp.delay, 3600);
+        assert_eq!(resp.actions.len(), 2);
+        assert_eq!(
+            resp.actions[0].action,
+            AdminAction::Withdraw {
+                round_id: 1,
+                amount: None,
+            }
+        );
+
+        let err = exec(&mut deps, &env, "admin1", ExecMsg::ExecuteAction { id: 1 }).unwrap_err();
+        assert_eq!(
+            err,
+            ContractError::TimelockNotExpired {
+                id: 1,
+                eta: env.block.time.seconds() + 3600,
+            }
+        );
+
+        // The owner spots the suspicious removal and cancels it
+        exec(&mut deps, &env, "admin1", ExecMsg::CancelAction { id: 2 }).unwrap_err();
+        exec(&mut deps, &env, "owner", ExecMsg::CancelAction { id: 2 }).unwrap();
+
+        env.block.time = env.block.time.plus_seconds(3600);
+        let resp = exec(&mut deps, &env, "admin1", ExecMsg::ExecuteAction { id: 1 }).unwrap();
+        assert_eq!(
+            resp.messages[0].msg,
+            BankMsg::Send {
+                to_address: "owner".to_string(),
+                amount: coins(10000, "inj"),
+            }
+            .into()
+        );
+        let err = exec(&mut deps, &env, "owner", ExecMsg::ExecuteAction { id: 2 }).unwrap_err();
+        assert_eq!(err, ContractError::QueuedActionNotExist { id: 2 });
+    }
+
+    #[test]
+    fn multisig_round_finalization() {
+        let (mut deps, mut env) = setup_round(1);
+        vote(&mut deps, &env, "user1", vec![1], vec![10000]).unwrap();
+
+        for admin in ["admin2", "admin3"] {
+            let msg = ExecMsg::AddAdmin {
+                admin: admin.to_string(),
+            };
+            exec(&mut deps, &env, "owner", msg).unwrap();
+        }
+        let err = exec(
+            &mut deps,
+            &env,
+            "owner",
+            ExecMsg::SetApprovalThreshold { threshold: 4 },
+        )
+        .unwrap_err();
+        assert_eq!(
+            err,
+            ContractError::InvalidThreshold {
+                threshold: 4,
+                admins: 3,
+            }
+        );
+        exec(
+            &mut deps,
+            &env,
+            "owner",
+            ExecMsg::SetApprovalThreshold { threshold: 0 },
+        )
+        .unwrap_err();
+        exec(
+            &mut deps,
+            &env,
+            "owner",
+            ExecMsg::SetApprovalThreshold { threshold: 2 },
+        )
+        .unwrap();
+
+        let err = exec(&mut deps, &env, "admin1", ExecMsg::EndRound { round_id: 1 }).unwrap_err();
+        assert_eq!(err, ContractError::ApprovalRequired { threshold: 2 });
+
+        let msg = ExecMsg::Propose {
+            action: ProposalAction::EndRound { round_id: 1 },
+        };
+        exec(&mut deps, &env, "admin1", msg).unwrap();
+
+        let msg = QueryMsg::Proposals {
+            start_after: None,
+            limit: None,
+        };
+        let resp = query(
+            deps.as_ref(),
+            env.clone(),
+            ContractQueryMsg::QGContract(msg),
+        )
+        .unwrap();
+        let resp: ProposalsResp = from_json(&resp).unwrap();
+        assert_eq!(resp.threshold, 2);
+        assert_eq!(resp.proposals[0].approvals, vec!["admin1".to_string()]);
+
+        let err = exec(&mut deps, &env, "admin1", ExecMsg::Approve { id: 1 }).unwrap_err();
+        assert_eq!(
+            err,
+            ContractError::AlreadyApproved {
+                id: 1,
+                address: Addr::unchecked("admin1"),
+            }
+        );
+        exec(&mut deps, &env, "user1", ExecMsg::Approve { id: 1 }).unwrap_err();
+        let err = exec(
+            &mut deps,
+            &env,
+            "admin2",
+            ExecMsg::ExecuteProposal { id: 1 },
+        )
+        .unwrap_err();
+        assert_eq!(
+            err,
+            ContractError::NotEnoughApprovals {
+                id: 1,
+                approvals: 1,
+                threshold: 2,
+            }
+        );
+        exec(&mut deps, &env, "admin3", ExecMsg::Approve { id: 1 }).unwrap();
+        exec(
+            &mut deps,
+            &env,
+            "admin2",
+            ExecMsg::ExecuteProposal { id: 1 },
+        )
+        .unwrap();
+
+        let resp = query(
+            deps.as_ref(),
+            env.clone(),
+            ContractQueryMsg::QGContract(QueryMsg::Round { round_id: 1 }),
+        )
+        .unwrap();
+        let round: Round = from_json(&resp).unwrap();
+        assert_eq!(round.status, RoundStatus::Finished);
+
+        let msg = QueryMsg::Proposals {
+            start_after: None,
+            limit: None,
+        };
+        let resp = query(
+            deps.as_ref(),
+            env.clone(),
+            ContractQueryMsg::QGContract(msg),
+        )
+        .unwrap();
+        let resp: ProposalsResp = from_json(&resp).unwrap();
+        assert!(resp.proposals.is_empty());
+
+        let err = exec(&mut deps, &env, "admin2", ExecMsg::Approve { id: 1 }).unwrap_err();
+        assert_eq!(err, ContractError::ProposalExecuted { id: 1 });
+
+        // Other payouts of the round need approvals as well
+        let msg = ExecMsg::Rollover {
+            round_id: 1,
+            next_round_id: 2,
+        };
+        let err = exec(&mut deps, &env, "admin1", msg).unwrap_err();
+        assert_eq!(err, ContractError::ApprovalRequired { threshold: 2 });
+        let err = exec(
+            &mut deps,
+            &env,
+            "admin1",
+            ExecMsg::SweepUnclaimed { round_id: 1 },
+        )
+        .unwrap_err();
+        assert_eq!(err, ContractError::ApprovalRequired { threshold: 2 });
+
+        // Approvals of removed admins stop counting
+        let msg = ExecMsg::Propose {
+            action: ProposalAction::Withdraw {
+                round_id: 1,
+                amount: None,
+            },
+        };
+        exec(&mut deps, &env, "admin1", msg).unwrap();
+        exec(&mut deps, &env, "admin3", ExecMsg::Approve { id: 2 }).unwrap();
+        let msg = ExecMsg::DelAdmin {
+            admin: "admin3".to_string(),
+        };
+        exec(&mut deps, &env, "owner", msg).unwrap();
+        let err = exec(
+            &mut deps,
+            &env,
+            "admin1",
+            ExecMsg::ExecuteProposal { id: 2 },
+        )
+        .unwrap_err();
+        assert_eq!(
+            err,
+            ContractError::NotEnoughApprovals {
+                id: 2,
+                approvals: 1,
+                threshold: 2,
+            }
+        );
+
+        // The remaining admins cannot drop below the threshold
+        let msg = ExecMsg::DelAdmin {
+            admin: "admin2".to_string(),
+        };
+        let err = exec(&mut deps, &env, "owner", msg).unwrap_err();
+        assert_eq!(
+            err,
+            ContractError::InvalidThreshold {
+                threshold: 2,
+                admins: 1,
+            }
+        );
+
+        // Executing needs no treasurer role, the approvals are enough, but the
+        // withdrawal still waits out the timelock
+        exec(
+            &mut deps,
+            &env,
+            "owner",
+            ExecMsg::SetTimelock { delay: 3600 },
+        )
+        .unwrap();
+        exec(&mut deps, &env, "admin2", ExecMsg::Approve { id: 2 }).unwrap();
+        let resp = exec(
+            &mut deps,
+            &env,
+            "admin2",
+            ExecMsg::ExecuteProposal { id: 2 },
+        )
+        .unwrap();
+        assert!(resp.messages.is_empty());
+        env.block.time = env.block.time.plus_seconds(3600);
+        let resp = exec(&mut deps, &env, "admin1", ExecMsg::ExecuteAction { id: 1 }).unwrap();
+        assert_eq!(
+            resp.messages[0].msg,
+            BankMsg::Send {
+                to_address: "owner".to_string(),
+                amount: coins(10000, "inj"),
+            }
+            .into()
+        );
+    }
+
+    #[test]
+    fn challenge_period_and_claims() {
+        let (mut deps, mut env) = setup_round(2);
+
+        let msg = ExecMsg::SetChallengePeriod {
+            round_id: 1,
+            challenge_period: 86400,
+        };
+        exec(&mut deps, &env, "admin1", msg).unwrap();
+
+        vote(&mut deps, &env, "user1", vec![1, 2], vec![10000, 40000]).unwrap();
+        exec(&mut deps, &env, "admin1", ExecMsg::EndRound { round_id: 1 }).unwrap();
+
+        let grant_of = |deps: &MockDeps, project_id| -> GrantResp {
+            let msg = QueryMsg::Grant {
+                round_id: 1,
+                project_id,
+            };
+            let resp = query(deps.as_ref(), mock_env(), ContractQueryMsg::QGContract(msg)).unwrap();
+            from_json(&resp).unwrap()
+        };
+        assert_eq!(grant_of(&deps, 1).grant.matching, 1333);
+        assert_eq!(grant_of(&deps, 2).grant.matching, 2666);
+
+        // Payouts are not available while the distribution can still be challenged
+        let err = exec(
+            &mut deps,
+            &env,
+            "admin1",
+            ExecMsg::Withdraw {
+                round_id: 1,
+                amount: None,
+            },
+        )
+        .unwrap_err();
+        assert_eq!(err, ContractError::RoundNotEnded { round_id: 1 });
+        let claim = ExecMsg::Claim {
+            round_id: 1,
+            project_id: 1,
+        };
+        let err = exec(&mut deps, &env, "owner1", claim.clone()).unwrap_err();
+        assert_eq!(err, ContractError::RoundNotEnded { round_id: 1 });
+
+        let msg = ExecMsg::BanProject {
+            round_id: 1,
+            project_id: 2,
+            banned: true,
+        };
+        exec(&mut deps, &env, "admin1", msg).unwrap();
+        assert_eq!(grant_of(&deps, 1).grant.matching, 4000);
+        assert_eq!(grant_of(&deps, 2).grant.matching, 0);
+
+        let err = exec(
+            &mut deps,
+            &env,
+            "anyone",
+            ExecMsg::LockDistribution { round_id: 1 },
+        )
+        .unwrap_err();
+        assert_eq!(
+            err,
+            ContractError::ChallengePeriodNotOver {
+                round_id: 1,
+                ends_at: env.block.time.seconds() + 86400,
+            }
+        );
+        env.block.time = env.block.time.plus_seconds(86400);
+        exec(
+            &mut deps,
+            &env,
+            "anyone",
+            ExecMsg::LockDistribution { round_id: 1 },
+        )
+        .unwrap();
+
+        // The distribution is locked now
+        let msg = ExecMsg::AdjustProjectArea {
+            round_id: 1,
+            project_id: 1,
+            area: 0,
+        };
+        let err = exec(&mut deps, &env, "admin1", msg).unwrap_err();
+        assert_eq!(err, ContractError::RoundNotChallenging { round_id: 1 });
+
+        exec(&mut deps, &env, "owner2", claim.clone()).unwrap_err();
+        let resp = exec(&mut deps, &env, "owner1", claim.clone()).unwrap();
+        assert_eq!(
+            resp.messages[0].msg,
+            BankMsg::Send {
+                to_address: "owner1".to_string(),
+                amount: coins(4000, "inj"),
+            }
+            .into()
+        );
+        let err = exec(&mut deps, &env, "owner1", claim).unwrap_err();
+        assert_eq!(err, ContractError::NothingToClaim { project_id: 1 });
+        assert_eq!(grant_of(&deps, 1).claimed, 4000);
+    }
+
+    #[test]
+    fn frozen_results_snapshot() {
+        let (mut deps, env) = setup_round(2);
+
+        vote(&mut deps, &env, "user1", vec![1, 2], vec![10000, 40000]).unwrap();
+        let resp = exec(&mut deps, &env, "admin1", ExecMsg::EndRound { round_id: 1 }).unwrap();
+
+        let msg = QueryMsg::Snapshot {
+            round_id: 1,
+            start_after: None,
+            limit: None,
+        };
+        let resp_query = query(
+            deps.as_ref(),
+            env.clone(),
+            ContractQueryMsg::QGContract(msg),
+        )
+        .unwrap();
+        let snapshot: SnapshotResp = from_json(&resp_query).unwrap();
+        let hash = hex::encode(keccak256(&to_json_vec(&snapshot.grants).unwrap()));
+        assert_eq!(snapshot.snapshot_hash, hash);
+        assert_eq!(snapshot.finalized_at, env.block.time.seconds());
+
+        let event = resp
+            .events
+            .iter()
+            .find(|event| event.ty == "finalize_round")
+            .unwrap();
+        assert!(event
+            .attributes
+            .iter()
+            .any(|attr| attr.key == "snapshot_hash" && attr.value == hash));
+
+        let msg = QueryMsg::Snapshot {
+            round_id: 1,
+            start_after: Some(1),
+            limit: None,
+        };
+        let resp_query = query(deps.as_ref(), env, ContractQueryMsg::QGContract(msg)).unwrap();
+        let page: SnapshotResp = from_json(&resp_query).unwrap();
+        assert_eq!(page.grants, snapshot.grants[1..].to_vec());
+    }
+
+    #[test]
+    fn merkle_claims() {
+        let (mut deps, mut env) = setup_round(2);
+
+        let msg = ExecMsg::SetPayoutMode {
+            round_id: 1,
+            payout_mode: PayoutMode::Merkle,
+        };
+        exec(&mut deps, &env, "admin1", msg).unwrap();
+        vote(&mut deps, &env, "user1", vec![1, 2], vec![10000, 40000]).unwrap();
+        exec(&mut deps, &env, "admin1", ExecMsg::EndRound { round_id: 1 }).unwrap();
+
+        // The tree is built from the frozen distribution
+        let msg = QueryMsg::Snapshot {
+            round_id: 1,
+            start_after: None,
+            limit: None,
+        };
+        let resp = query(
+            deps.as_ref(),
+            env.clone(),
+            ContractQueryMsg::QGContract(msg),
+        )
+        .unwrap();
+        let snapshot: SnapshotResp = from_json(&resp).unwrap();
+        assert_eq!(
+            snapshot.snapshot_hash,
+            hex::encode(keccak256(&to_json_vec(&snapshot.grants).unwrap()))
+        );
+        let matching: Vec<u128> = snapshot.grants.iter().map(|grant| grant.matching).collect();
+        assert_eq!(matching, vec![1333, 2666]);
+        // ... without storing a grant per project
+        let msg = QueryMsg::Grant {
+            round_id: 1,
+            project_id: 1,
+        };
+        query(
+            deps.as_ref(),
+            env.clone(),
+            ContractQueryMsg::QGContract(msg),
+        )
+        .unwrap_err();
+
+        let leaves = [
+            merkle::leaf_hash(0, "owner1", 1333),
+            merkle::leaf_hash(1, "owner2", 2666),
+        ];
+        let root = merkle::hash_pair(&leaves[0], &leaves[1]);
+        let deadline = env.block.time.seconds() + 1000;
+        let set_root = |total_amount: u128| ExecMsg::SetMerkleRoot {
+            round_id: 1,
+            merkle_root: hex::encode(root),
+            total_amount: Uint128::from(total_amount),
+            claim_deadline: deadline,
+        };
+        let err = exec(&mut deps, &env, "admin1", set_root(4000)).unwrap_err();
+        assert_eq!(
+            err,
+            ContractError::InvalidAmount {
+                expected: 3999,
+                actual: 4000,
+            }
+        );
+        exec(&mut deps, &env, "admin1", set_root(3999)).unwrap();
+
+        let claim = |index: u64, amount: u128, sibling: usize| ExecMsg::ClaimMerkle {
+            round_id: 1,
+            index,
+            amount: Uint128::from(amount),
+            proof: vec![hex::encode(leaves[sibling])],
+        };
+        let err = exec(&mut deps, &env, "owner1", claim(0, 4000, 1)).unwrap_err();
+        assert_eq!(err, ContractError::InvalidMerkleProof);
+        exec(&mut deps, &env, "owner2", claim(0, 1333, 1)).unwrap_err();
+        let resp = exec(&mut deps, &env, "owner1", claim(0, 1333, 1)).unwrap();
+        assert_eq!(
+            resp.messages[0].msg,
+            BankMsg::Send {
+                to_address: "owner1".to_string(),
+                amount: coins(1333, "inj"),
+            }
+            .into()
+        );
+        let err = exec(&mut deps, &env, "owner1", claim(0, 1333, 1)).unwrap_err();
+        assert_eq!(err, ContractError::AlreadyClaimed { index: 0 });
+
+        let msg = QueryMsg::MerkleClaim {
+            round_id: 1,
+            index: 0,
+        };
+        let resp = query(
+            deps.as_ref(),
+            env.clone(),
+            ContractQueryMsg::QGContract(msg),
+        )
+        .unwrap();
+        let resp: MerkleClaimResp = from_json(&resp).unwrap();
+        assert!(resp.claimed);
+
+        let err = exec(
+            &mut deps,
+            &env,
+            "admin1",
+            ExecMsg::SweepUnclaimed { round_id: 1 },
+        )
+        .unwrap_err();
+        assert_eq!(
+            err,
+            ContractError::ClaimDeadline {
+                round_id: 1,
+                deadline,
+            }
+        );
+
+        env.block.time = env.block.time.plus_seconds(1000);
+        let err = exec(&mut deps, &env, "owner2", claim(1, 2666, 0)).unwrap_err();
+        assert_eq!(
+            err,
+            ContractError::ClaimDeadline {
+                round_id: 1,
+                deadline,
+            }
+        );
+        let resp = exec(
+            &mut deps,
+            &env,
+            "admin1",
+            ExecMsg::SweepUnclaimed { round_id: 1 },
+        )
+        .unwrap();
+        assert_eq!(
+            resp.messages[0].msg,
+            BankMsg::Send {
+                to_address: "owner".to_string(),
+                amount: coins(2666, "inj"),
+            }
+            .into()
+        );
+    }
+
+    #[test]
+    fn vested_payouts() {
+        let (mut deps, mut env) = setup_round(2);
+
+        let msg = ExecMsg::SetVesting {
+            round_id: 1,
+            cliff: 2000,
+            duration: 1000,
+        };
+        let err = exec(&mut deps, &env, "admin1", msg).unwrap_err();
+        assert_eq!(
+            err,
+            ContractError::InvalidVestingSchedule {
+                cliff: 2000,
+                duration: 1000,
+            }
+        );
+        let msg = ExecMsg::SetVesting {
+            round_id: 1,
+            cliff: 1000,
+            duration: 4000,
+        };
+        exec(&mut deps, &env, "admin1", msg).unwrap();
+
+        vote(&mut deps, &env, "user1", vec![1, 2], vec![10000, 40000]).unwrap();
+        exec(&mut deps, &env, "admin1", ExecMsg::EndRound { round_id: 1 }).unwrap();
+
+        let claim = |project_id| ExecMsg::ClaimVested {
+            round_id: 1,
+            project_id,
+        };
+        let err = exec(
+            &mut deps,
+            &env,
+            "owner1",
+            ExecMsg::Claim {
+                round_id: 1,
+                project_id: 1,
+            },
+        )
+        .unwrap_err();
+        assert_eq!(err, ContractError::WrongPayoutMode { round_id: 1 });
+        let err = exec(&mut deps, &env, "owner1", claim(1)).unwrap_err();
+        assert_eq!(err, ContractError::NothingToClaim { project_id: 1 });
+
+        // A quarter of the schedule has passed at the cliff
+        env.block.time = env.block.time.plus_seconds(1000);
+        let resp = exec(&mut deps, &env, "owner1", claim(1)).unwrap();
+        assert_eq!(
+            resp.messages[0].msg,
+            BankMsg::Send {
+                to_address: "owner1".to_string(),
+                amount: coins(333, "inj"),
+            }
+            .into()
+        );
+
+        env.block.time = env.block.time.plus_seconds(1000);
+        let msg = ExecMsg::HaltVesting {
+            round_id: 1,
+            project_id: 2,
+        };
+        exec(&mut deps, &env, "owner2", msg.clone()).unwrap_err();
+        let pause = ExecMsg::Pause { round_id: Some(1) };
+        exec(&mut deps, &env, "owner", pause).unwrap();
+        let err = exec(&mut deps, &env, "admin1", msg.clone()).unwrap_err();
+        assert_eq!(err, ContractError::RoundPaused { round_id: 1 });
+        let unpause = ExecMsg::Unpause { round_id: Some(1) };
+        exec(&mut deps, &env, "owner", unpause).unwrap();
+        let resp = exec(&mut deps, &env, "admin1", msg.clone()).unwrap();
+        assert_eq!(
+            resp.messages[0].msg,
+            BankMsg::Send {
+                to_address: "owner".to_string(),
+                amount: coins(1333, "inj"),
+            }
+            .into()
+        );
+        let err = exec(&mut deps, &env, "admin1", msg).unwrap_err();
+        assert_eq!(err, ContractError::VestingHalted { project_id: 2 });
+
+        // The halted project keeps what vested before the halt
+        env.block.time = env.block.time.plus_seconds(10000);
+        let msg = QueryMsg::Vesting {
+            round_id: 1,
+            project_id: 2,
+        };
+        let resp = query(
+            deps.as_ref(),
+            env.clone(),
+            ContractQueryMsg::QGContract(msg),
+        )
+        .unwrap();
+        let resp: VestingResp = from_json(&resp).unwrap();
+        assert_eq!(resp.vested, 1333);
+        assert_eq!(resp.releasable, 1333);
+        exec(&mut deps, &env, "owner2", claim(2)).unwrap();
+        let err = exec(&mut deps, &env, "owner2", claim(2)).unwrap_err();
+        assert_eq!(err, ContractError::NothingToClaim { project_id: 2 });
+
+        let resp = exec(&mut deps, &env, "owner1", claim(1)).unwrap();
+        assert_eq!(
+            resp.messages[0].msg,
+            BankMsg::Send {
+                to_address: "owner1".to_string(),
+                amount: coins(1000, "inj"),
+            }
+            .into()
+        );
+    }
+
+    #[test]
+    fn milestone_release() {
+        let (mut deps, env) = setup_round(2);
+
+        let msg = ExecMsg::SetPayoutMode {
+            round_id: 1,
+            payout_mode: PayoutMode::Milestone,
+        };
+        exec(&mut deps, &env, "admin1", msg).unwrap();
+        vote(&mut deps, &env, "user1", vec![1, 2], vec![10000, 40000]).unwrap();
+        exec(&mut deps, &env, "admin1", ExecMsg::EndRound { round_id: 1 }).unwrap();
+
+        let add = |project_id, percentage| ExecMsg::AddMilestone {
+            round_id: 1,
+            project_id,
+            description: format!("{}% done", percentage),
+            percentage,
+        };
+        exec(&mut deps, &env, "owner1", add(1, 40)).unwrap_err();
+        exec(&mut deps, &env, "admin1", add(1, 40)).unwrap();
+        exec(&mut deps, &env, "admin1", add(1, 60)).unwrap();
+        let err = exec(&mut deps, &env, "admin1", add(1, 1)).unwrap_err();
+        assert_eq!(
+            err,
+            ContractError::InvalidMilestonePercentage { total: 101 }
+        );
+        exec(&mut deps, &env, "admin1", add(2, 50)).unwrap();
+
+        let claim = |project_id| ExecMsg::ClaimMilestones {
+            round_id: 1,
+            project_id,
+        };
+        let err = exec(&mut deps, &env, "owner1", claim(1)).unwrap_err();
+        assert_eq!(err, ContractError::NothingToClaim { project_id: 1 });
+
+        let complete = |project_id, milestone_id| ExecMsg::CompleteMilestone {
+            round_id: 1,
+            project_id,
+            milestone_id,
+        };
+        exec(&mut deps, &env, "admin1", complete(1, 1)).unwrap();
+        let err = exec(&mut deps, &env, "admin1", complete(1, 1)).unwrap_err();
+        assert_eq!(err, ContractError::MilestoneCompleted { id: 1 });
+        let err = exec(&mut deps, &env, "admin1", complete(1, 3)).unwrap_err();
+        assert_eq!(err, ContractError::MilestoneNotExist { id: 3 });
+        let resp = exec(&mut deps, &env, "owner1", claim(1)).unwrap();
+        assert_eq!(
+            resp.messages[0].msg,
+            BankMsg::Send {
+                to_address: "owner1".to_string(),
+                amount: coins(533, "inj"),
+            }
+            .into()
+        );
+
+        // Unreleased matching of project 1 goes back to the treasury
+        let msg = ExecMsg::CloseMilestones {
+            round_id: 1,
+            project_id: 1,
+            next_round_id: None,
+        };
+        exec(&mut deps, &env, "owner", ExecMsg::Pause { round_id: None }).unwrap();
+        let err = exec(&mut deps, &env, "admin1", msg.clone()).unwrap_err();
+        assert_eq!(err, ContractError::Paused);
+        exec(
+            &mut deps,
+            &env,
+            "owner",
+            ExecMsg::Unpause { round_id: None },
+        )
+        .unwrap();
+        let resp = exec(&mut deps, &env, "admin1", msg).unwrap();
+        assert_eq!(
+            resp.messages[0].msg,
+            BankMsg::Send {
+                to_address: "owner".to_string(),
+                amount: coins(800, "inj"),
+            }
+            .into()
+        );
+        let err = exec(&mut deps, &env, "admin1", complete(1, 2)).unwrap_err();
+        assert_eq!(err, ContractError::MilestonesClosed { project_id: 1 });
+
+        // ... and project 2's into the next round
+        let msg = ExecMsg::StartRound {
+            tax_adjustment_multiplier: 5,
+            donation_denom: "inj".to_string(),
+            voting_unit: Uint128::from(1_000_000_000_000_000_000u128),
+            fund: Uint128::from(1000u128),
+            pubkey: vec![],
+            matching_strategy: None,
+        };
+        exec(&mut deps, &env, "admin1", msg).unwrap();
+        exec(&mut deps, &env, "admin1", complete(2, 1)).unwrap();
+        let msg = ExecMsg::CloseMilestones {
+            round_id: 1,
+            project_id: 2,
+            next_round_id: Some(2),
+        };
+        let resp = exec(&mut deps, &env, "admin1", msg).unwrap();
+        assert!(resp.messages.is_empty());
+
+        let msg = QueryMsg::Round { round_id: 2 };
+        let resp = query(
+            deps.as_ref(),
+            env.clone(),
+            ContractQueryMsg::QGContract(msg),
+        )
+        .unwrap();
+        let round: Round = from_json(&resp).unwrap();
+        assert_eq!(round.fund, Uint128::from(2333u128));
+
+        let msg = QueryMsg::Milestones {
+            round_id: 1,
+            project_id: 2,
+        };
+        let resp = query(
+            deps.as_ref(),
+            env.clone(),
+            ContractQueryMsg::QGContract(msg),
+        )
+        .unwrap();
+        let resp: MilestonesResp = from_json(&resp).unwrap();
+        assert_eq!(resp.released, 1333);
+        assert_eq!(resp.returned, Some(1333));
+        exec(&mut deps, &env, "owner2", claim(2)).unwrap();
+    }
+
+    #[test]
+    fn rollover_to_next_round() {
+        let (mut deps, mut env) = setup_round(2);
+
+        vote(&mut deps, &env, "user1", vec![1, 2], vec![10000, 40000]).unwrap();
+        exec(&mut deps, &env, "admin1", ExecMsg::EndRound { round_id: 1 }).unwrap();
+        let claim = |project_id| ExecMsg::Claim {
+            round_id: 1,
+            project_id,
+        };
+        exec(&mut deps, &env, "owner1", claim(1)).unwrap();
+
+        let rollover = |next_round_id| ExecMsg::Rollover {
+            round_id: 1,
+            next_round_id,
+        };
+        exec(&mut deps, &env, "user1", rollover(2)).unwrap_err();
+
+        let msg = ExecMsg::StartRound {
+            tax_adjustment_multiplier: 5,
+            donation_denom: "inj".to_string(),
+            voting_unit: Uint128::from(1_000_000_000_000_000_000u128),
+            fund: Uint128::from(1000u128),
+            pubkey: vec![],
+            matching_strategy: None,
+        };
+        exec(&mut deps, &env, "admin1", msg).unwrap();
+        let err = exec(&mut deps, &env, "admin1", rollover(2)).unwrap_err();
+        assert_eq!(err, ContractError::ClaimDeadlineNotSet { round_id: 1 });
+        let deadline = env.block.time.seconds() + 1000;
+        let msg = ExecMsg::SetClaimDeadline {
+            round_id: 1,
+            claim_deadline: deadline,
+        };
+        exec(&mut deps, &env, "admin1", msg).unwrap();
+        let err = exec(&mut deps, &env, "admin1", rollover(2)).unwrap_err();
+        assert_eq!(
+            err,
+            ContractError::ClaimDeadline {
+                round_id: 1,
+                deadline,
+            }
+        );
+
+        env.block.time = env.block.time.plus_seconds(1000);
+        let err = exec(&mut deps, &env, "owner2", claim(2)).unwrap_err();
+        assert_eq!(
+            err,
+            ContractError::ClaimDeadline {
+                round_id: 1,
+                deadline,
+            }
+        );
+        let err = exec(&mut deps, &env, "admin1", rollover(1)).unwrap_err();
+        assert_eq!(err, ContractError::RoundNotInVoting { round_id: 1 });
+        // Rounding dust and the grant owner2 never claimed
+        exec(&mut deps, &env, "admin1", rollover(2)).unwrap();
+        let err = exec(&mut deps, &env, "admin1", rollover(2)).unwrap_err();
+        assert_eq!(err, ContractError::RoundRolledOver { round_id: 1 });
+        let err = exec(&mut deps, &env, "owner2", claim(2)).unwrap_err();
+        assert_eq!(err, ContractError::RoundRolledOver { round_id: 1 });
+
+        let round_of = |deps: &MockDeps, round_id| -> Round {
+            let msg = QueryMsg::Round { round_id };
+            let resp = query(deps.as_ref(), mock_env(), ContractQueryMsg::QGContract(msg)).unwrap();
+            from_json(&resp).unwrap()
+        };
+        let round = round_of(&deps, 1);
+        assert_eq!(round.rollover_to, 2);
+        assert_eq!(round.rolled_over, 2667);
+        assert_eq!(round.matching_paid, 4000);
+        let round = round_of(&deps, 2);
+        assert_eq!(round.fund, Uint128::from(3667u128));
+        assert_eq!(round.rolled_in, 2667);
+        assert_eq!(round.deposited, 2667);
+    }
+
+    #[test]
+    fn matching_deposits() {
+        let (mut deps, env) = setup_round(2);
+
+        let start = ExecMsg::StartRound {
+            tax_adjustment_multiplier: 5,
+            donation_denom: "inj".to_string(),
+            voting_unit: Uint128::from(1_000_000_000_000_000_000u128),
+            fund: Uint128::from(1000u128),
+            pubkey: vec![],
+            matching_strategy: None,
+        };
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("admin1", &coins(1001, "inj")),
+            ContractExecMsg::QGContract(start.clone()),
+        )
+        .unwrap_err();
+        assert_eq!(
+            err,
+            ContractError::DepositExceedsFund {
+                round_id: 2,
+                remaining: 1000,
+            }
+        );
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("admin1", &coins(400, "inj")),
+            ContractExecMsg::QGContract(start),
+        )
+        .unwrap();
+        let msg = ExecMsg::BatchUploadProject {
+            round_id: 2,
+            owner_addresses: vec!["owner1".to_string()],
+        };
+        exec(&mut deps, &env, "admin1", msg).unwrap();
+
+        // Sybil adjustments have to wait until voting is over
+        let msg = ExecMsg::BanProject {
+            round_id: 2,
+            project_id: 1,
+            banned: true,
+        };
+        let err = exec(&mut deps, &env, "admin1", msg).unwrap_err();
+        assert_eq!(err, ContractError::RoundNotChallenging { round_id: 2 });
+
+        let err = exec(&mut deps, &env, "admin1", ExecMsg::EndRound { round_id: 2 }).unwrap_err();
+        assert_eq!(
+            err,
+            ContractError::RoundNotFunded {
+                round_id: 2,
+                fund: 1000,
+                deposited: 400,
+            }
+        );
+
+        let fund = |amount| mock_info("anyone", &coins(amount, "inj"));
+        let msg = ContractExecMsg::QGContract(ExecMsg::FundRound { round_id: 2 });
+        let err = execute(deps.as_mut(), env.clone(), fund(601), msg.clone()).unwrap_err();
+        assert_eq!(
+            err,
+            ContractError::DepositExceedsFund {
+                round_id: 2,
+                remaining: 600,
+            }
+        );
+        exec(
+            &mut deps,
+            &env,
+            "owner",
+            ExecMsg::Pause { round_id: Some(2) },
+        )
+        .unwrap();
+        let err = execute(deps.as_mut(), env.clone(), fund(600), msg.clone()).unwrap_err();
+        assert_eq!(err, ContractError::RoundPaused { round_id: 2 });
+        exec(
+            &mut deps,
+            &env,
+            "owner",
+            ExecMsg::Unpause { round_id: Some(2) },
+        )
+        .unwrap();
+        execute(deps.as_mut(), env.clone(), fund(600), msg.clone()).unwrap();
+        exec(&mut deps, &env, "admin1", ExecMsg::EndRound { round_id: 2 }).unwrap();
+        let err = execute(deps.as_mut(), env.clone(), fund(1), msg).unwrap_err();
+        assert_eq!(err, ContractError::RoundNotInVoting { round_id: 2 });
+    }
+
+    #[test]
+    fn matching_strategies() {
+        let matching = |strategy, votes: Vec<(&str, Vec<u64>, Vec<u128>)>| -> Vec<u128> {
+            let (mut deps, env) = setup_round_with(3, Some(strategy));
+            for (voter, project_ids, amounts) in votes {
+                vote(&mut deps, &env, voter, project_ids, amounts).unwrap();
+            }
+            let msg = QueryMsg::Distribution { round_id: 1 };
+            let resp = query(deps.as_ref(), env, ContractQueryMsg::QGContract(msg)).unwrap();
+            let resp: DistributionResp = from_json(&resp).unwrap();
+            resp.grants.iter().map(|grant| grant.matching).collect()
+        };
+
+        let (mut deps, env) = setup_round(0);
+        for strategy in [
+            MatchingStrategy::Capped { cap_bps: 0 },
+            MatchingStrategy::Capped { cap_bps: 10001 },
+            MatchingStrategy::Pairwise { threshold: 0 },
+        ] {
+            let msg = ExecMsg::StartRound {
+                tax_adjustment_multiplier: 5,
+                donation_denom: "inj".to_string(),
+                voting_unit: Uint128::from(1_000_000_000_000_000_000u128),
+                fund: Uint128::from(4000u128),
+                pubkey: vec![],
+                matching_strategy: Some(strategy),
+            };
+            let err = exec(&mut deps, &env, "admin1", msg).unwrap_err();
+            assert_eq!(err, ContractError::InvalidMatchingStrategy);
+        }
+
+        let whale = || {
+            vec![
+                ("user1", vec![1], vec![90000]),
+                ("user2", vec![2], vec![10000]),
+            ]
+        };
+        assert_eq!(
+            matching(MatchingStrategy::Classic, whale()),
+            vec![3000, 1000, 0]
+        );
+        // The excess over the cap goes to the other projects
+        assert_eq!(
+            matching(MatchingStrategy::Capped { cap_bps: 5000 }, whale()),
+            vec![2000, 2000, 0]
+        );
+        assert_eq!(
+            matching(MatchingStrategy::Linear, whale()),
+            vec![3600, 400, 0]
+        );
+
+        // user1 and user2 co-fund two projects, so their pair is discounted more
+        let colluding = || {
+            vec![
+                ("user1", vec![1, 2], vec![10000, 10000]),
+                ("user2", vec![1, 2], vec![10000, 10000]),
+                ("user3", vec![3], vec![10000]),
+                ("user4", vec![3], vec![10000]),
+            ]
+        };
+        assert_eq!(
+            matching(MatchingStrategy::Classic, colluding()),
+            vec![1333, 1333, 1333]
+        );
+        assert_eq!(
+            matching(MatchingStrategy::Pairwise { threshold: 10000 }, colluding()),
+            vec![1142, 1142, 1714]
+        );
+    }
+
+    #[test]
+    fn cluster_matching() {
+        let (mut deps, env) = setup_round_with(2, Some(MatchingStrategy::Cluster));
+
+        vote(&mut deps, &env, "user1", vec![1], vec![10000]).unwrap();
+        vote(&mut deps, &env, "user2", vec![1], vec![10000]).unwrap();
+        vote(&mut deps, &env, "user3", vec![2], vec![10000]).unwrap();
+        vote(&mut deps, &env, "user4", vec![2], vec![10000]).unwrap();
+
+        let distribution = |deps: &MockDeps| -> DistributionResp {
+            let msg = QueryMsg::Distribution { round_id: 1 };
+            let resp = query(deps.as_ref(), mock_env(), ContractQueryMsg::QGContract(msg)).unwrap();
+            from_json(&resp).unwrap()
+        };
+        let resp = distribution(&deps);
+        assert_eq!(resp.grants[0].matching, 2000);
+        assert_eq!(resp.grants[1].matching, 2000);
+
+        let msg = ExecMsg::UploadVoterClusters {
+            round_id: 1,
+            voters: vec!["user1".to_string(), "user2".to_string()],
+            clusters: vec![1],
+        };
+        let err = exec(&mut deps, &env, "admin1", msg).unwrap_err();
+        assert_eq!(
+            err,
+            ContractError::LengthNotMatch {
+                expected: 2,
+                actual: 1,
+            }
+        );
+        let msg = ExecMsg::UploadVoterClusters {
+            round_id: 1,
+            voters: vec!["user1".to_string(), "user2".to_string()],
+            clusters: vec![1, 1],
+        };
+        exec(&mut deps, &env, "user1", msg.clone()).unwrap_err();
+        exec(&mut deps, &env, "admin1", msg).unwrap();
+
+        // user1 and user2 now count as a single contributor to project 1
+        let resp = distribution(&deps);
+        assert_eq!(resp.grants[0].area, 1414);
+        assert_eq!(resp.grants[1].area, 2000);
+        assert_eq!(resp.eligible_area, 3414);
+        assert_eq!(resp.grants[0].matching, 1656);
+        assert_eq!(resp.grants[1].matching, 2343);
+
+        // Areas follow reassignments and later votes without rescanning the round
+        let msg = ExecMsg::UploadVoterClusters {
+            round_id: 1,
+            voters: vec!["user2".to_string()],
+            clusters: vec![2],
+        };
+        exec(&mut deps, &env, "admin1", msg).unwrap();
+        assert_eq!(distribution(&deps).grants[0].area, 2000);
+        vote(&mut deps, &env, "user1", vec![1], vec![10000]).unwrap();
+        assert_eq!(distribution(&deps).grants[0].area, 2414);
+
+        exec(&mut deps, &env, "admin1", ExecMsg::EndRound { round_id: 1 }).unwrap();
+        let msg = ExecMsg::UploadVoterClusters {
+            round_id: 1,
+            voters: vec!["user3".to_string()],
+            clusters: vec![2],
+        };
+        let err = exec(&mut deps, &env, "admin1", msg).unwrap_err();
+        assert_eq!(err, ContractError::SnapshotFrozen { round_id: 1 });
+    }
+
+    #[test]
+    fn pairwise_incremental_areas() {
+        let strategy = MatchingStrategy::Pairwise { threshold: 10000 };
+        let (mut deps, env) = setup_round_with(3, Some(strategy));
+
+        let areas = |deps: &MockDeps| -> Vec<u128> {
+            let msg = QueryMsg::PairwiseAreas {
+                round_id: 1,
+                start_after: None,
+                limit: None,
+            };
+            let resp = query(deps.as_ref(), mock_env(), ContractQueryMsg::QGContract(msg)).unwrap();
+            let resp: PairwiseAreasResp = from_json(&resp).unwrap();
+            assert_eq!(resp.threshold, 10000);
+            resp.areas.iter().map(|area| area.area).collect()
+        };
+
+        // A single voter does not earn any pairwise subsidy
+        vote(&mut deps, &env, "user1", vec![1], vec![10000]).unwrap();
+        assert_eq!(areas(&deps), vec![0, 0, 0]);
+        vote(&mut deps, &env, "user2", vec![1], vec![2500]).unwrap();
+        assert_eq!(areas(&deps), vec![333333, 0, 0]);
+        vote(&mut deps, &env, "user2", vec![1], vec![7500]).unwrap();
+        assert_eq!(areas(&deps), vec![500000, 0, 0]);
+
+        // Funding a second project together discounts the pair on both
+        vote(&mut deps, &env, "user1", vec![2], vec![10000]).unwrap();
+        vote(&mut deps, &env, "user2", vec![2], vec![10000]).unwrap();
+        assert_eq!(areas(&deps), vec![333333, 333333, 0]);
+        vote(&mut deps, &env, "user3", vec![3], vec![10000]).unwrap();
+        vote(&mut deps, &env, "user4", vec![3], vec![10000]).unwrap();
+        assert_eq!(areas(&deps), vec![333333, 333333, 500000]);
+
+        let msg = QueryMsg::Distribution { round_id: 1 };
+        let resp = query(
+            deps.as_ref(),
+            env.clone(),
+            ContractQueryMsg::QGContract(msg),
+        )
+        .unwrap();
+        let resp: DistributionResp = from_json(&resp).unwrap();
+        assert_eq!(resp.eligible_area, 1166666);
+        let matching: Vec<u128> = resp.grants.iter().map(|grant| grant.matching).collect();
+        assert_eq!(matching, vec![1142, 1142, 1714]);
+
+        // Migrating to more decimals rebuilds the pairwise state, later votes keep it consistent
+        migrate(
+            deps.as_mut(),
+            env.clone(),
+            MigrateMsg { precision: Some(4) },
+        )
+        .unwrap();
+        assert_eq!(areas(&deps), vec![333333333333, 333333333333, 500000000000]);
+        vote(&mut deps, &env, "user2", vec![1], vec![3]).unwrap();
+        vote(&mut deps, &env, "user3", vec![1], vec![10000]).unwrap();
+        assert_eq!(
+            areas(&deps),
+            vec![1333403690025, 333316778599, 500000000000]
+        );
+    }
+
+    #[test]
+    fn pairwise_limits() {
+        let strategy = MatchingStrategy::Pairwise { threshold: 10000 };
+        let (mut deps, env) = setup_round_with(3, Some(strategy));
+
+        let upload = |count: usize| ExecMsg::BatchUploadProject {
+            round_id: 1,
+            owner_addresses: vec!["owner".to_string(); count],
+        };
+        exec(&mut deps, &env, "admin1", upload(17)).unwrap();
+        let err = exec(&mut deps, &env, "admin1", upload(1)).unwrap_err();
+        assert_eq!(
+            err,
+            ContractError::PairwiseProjectsExceeded {
+                round_id: 1,
+                limit: 20,
+            }
+        );
+
+        for voter in 0..50 {
+            vote(
+                &mut deps,
+                &env,
+                &format!("user{voter}"),
+                vec![1],
+                vec![10000],
+            )
+            .unwrap();
+        }
+        let err = vote(&mut deps, &env, "user50", vec![1], vec![10000]).unwrap_err();
+        assert_eq!(
+            err,
+            ContractError::PairwiseVotersExceeded {
+                project_id: 1,
+                limit: 50,
+            }
+        );
+        // Voters already counted can keep adding to it
+        vote(&mut deps, &env, "user0", vec![1], vec![10000]).unwrap();
+
+        // Areas follow from the votes, so they can't be overridden
+        let msg = ExecMsg::SetChallengePeriod {
+            round_id: 1,
+            challenge_period: 86400,
+        };
+        exec(&mut deps, &env, "admin1", msg).unwrap();
+        exec(&mut deps, &env, "admin1", ExecMsg::EndRound { round_id: 1 }).unwrap();
+        let msg = ExecMsg::AdjustProjectArea {
+            round_id: 1,
+            project_id: 1,
+            area: 0,
+        };
+        let err = exec(&mut deps, &env, "admin1", msg).unwrap_err();
+        assert_eq!(err, ContractError::WrongMatchingStrategy { round_id: 1 });
+    }
+
+    #[test]
+    fn fixed_point_precision() {
+        let (mut deps, env) = setup_round(2);
+        let round_of = |deps: &MockDeps, round_id| -> Round {
+            let msg = QueryMsg::Round { round_id };
+            let resp = query(deps.as_ref(), mock_env(), ContractQueryMsg::QGContract(msg)).unwrap();
+            from_json(&resp).unwrap()
+        };
+        let project_of = |deps: &MockDeps, project_id| -> Project {
+            let msg = QueryMsg::Project {
+                round_id: 1,
+                project_id,
+            };
+            let resp = query(deps.as_ref(), mock_env(), ContractQueryMsg::QGContract(msg)).unwrap();
+            from_json(&resp).unwrap()
+        };
+
+        // Legacy rounds keep one decimal: a donation of 2 only counts as sqrt(2) = 1.4
+        assert_eq!(round_of(&deps, 1).precision, 1);
+        vote(&mut deps, &env, "user1", vec![1, 2], vec![10000, 2]).unwrap();
+        assert_eq!(project_of(&deps, 1).area, 1000);
+        assert_eq!(project_of(&deps, 2).area, 14);
+
+        // Rounds stored before precision existed are read as legacy
+        let round = String::from_utf8(to_json_vec(&round_of(&deps, 1)).unwrap()).unwrap();
+        let legacy = round.replace("\"precision\":1,", "");
+        assert_ne!(legacy, round);
+        let legacy: Round = from_json(legacy.as_bytes()).unwrap();
+        assert_eq!(legacy.precision, 1);
+        migrate(deps.as_mut(), env.clone(), MigrateMsg { precision: None }).unwrap();
+        assert_eq!(round_of(&deps, 1).precision, 1);
+
+        let err = migrate(
+            deps.as_mut(),
+            env.clone(),
+            MigrateMsg {
+                precision: Some(10),
+            },
+        )
+        .unwrap_err();
+        assert_eq!(err, ContractError::InvalidPrecision { precision: 10 });
+        migrate(
+            deps.as_mut(),
+            env.clone(),
+            MigrateMsg { precision: Some(4) },
+        )
+        .unwrap();
+        let round = round_of(&deps, 1);
+        assert_eq!(round.precision, 4);
+        assert_eq!(round.total_area, 1014000);
+        assert_eq!(project_of(&deps, 1).area, 1000000);
+
+        // New votes use four decimals
+        vote(&mut deps, &env, "user2", vec![2], vec![2]).unwrap();
+        assert_eq!(project_of(&deps, 2).area, 14000 + 14142);
+
+        let msg = ExecMsg::SetPrecision { precision: 6 };
+        exec(&mut deps, &env, "admin1", msg.clone()).unwrap_err();
+        let err = exec(
+            &mut deps,
+            &env,
+            "owner",
+            ExecMsg::SetPrecision { precision: 0 },
+        )
+        .unwrap_err();
+        assert_eq!(err, ContractError::InvalidPrecision { precision: 0 });
+        exec(&mut deps, &env, "owner", msg).unwrap();
+        let msg = ExecMsg::StartRound {
+            tax_adjustment_multiplier: 5,
+            donation_denom: "inj".to_string(),
+            voting_unit: Uint128::from(1_000_000_000_000_000_000u128),
+            fund: Uint128::from(1000u128),
+            pubkey: vec![],
+            matching_strategy: None,
+        };
+        exec(&mut deps, &env, "admin1", msg).unwrap();
+        assert_eq!(round_of(&deps, 2).precision, 6);
+
+        // Linear areas are counted in votes and don't depend on the precision
+        let (mut deps, env) = setup_round_with(2, Some(MatchingStrategy::Linear));
+        vote(&mut deps, &env, "user1", vec![1], vec![10000]).unwrap();
+        assert_eq!(project_of(&deps, 1).area, 10000);
+        migrate(
+            deps.as_mut(),
+            env.clone(),
+            MigrateMsg { precision: Some(4) },
+        )
+        .unwrap();
+        let round = round_of(&deps, 1);
+        assert_eq!(round.precision, 4);
+        assert_eq!(round.total_area, 10000);
+        assert_eq!(project_of(&deps, 1).area, 10000);
+        vote(&mut deps, &env, "user2", vec![1], vec![10000]).unwrap();
+        vote(&mut deps, &env, "user1", vec![2], vec![5000]).unwrap();
+        assert_eq!(project_of(&deps, 1).area, 20000);
+        assert_eq!(round_of(&deps, 1).total_area, 25000);
+
+        // Later migrations report the precision in effect
+        let resp = migrate(deps.as_mut(), env, MigrateMsg { precision: None }).unwrap();
+        assert_eq!(resp.events[0].attributes[0].value, "4");
+    }
+
+    #[test]
+    fn weight_curves() {
+        let (mut deps, env) = setup_round(2);
+
+        let set = |deps: &mut MockDeps, weight_curve| {
+            let msg = ExecMsg::SetWeightCurve {
+                round_id: 1,
+                weight_curve,
+            };
+            exec(deps, &env, "admin1", msg)
+        };
+        for weight_curve in [
+            WeightCurve::Linear {
+                slope: Decimal::percent(10),
+                cap: Decimal::percent(50),
+            },
+            WeightCurve::Piecewise { steps: vec![] },
+            WeightCurve::Piecewise {
+                steps: vec![
+                    WeightStep {
+                        min_score: 100,
+                        weight: Decimal::percent(150),
+                    },
+                    WeightStep {
+                        min_score: 100,
+                        weight: Decimal::percent(200),
+                    },
+                ],
+            },
+            WeightCurve::Piecewise {
+                steps: vec![WeightStep {
+                    min_score: 100,
+                    weight: Decimal::zero(),
+                }],
+            },
+            WeightCurve::Constant {
+                weight: Decimal::zero(),
+            },
+            WeightCurve::Constant {
+                weight: Decimal::percent(50),
+            },
+        ] {
+            let err = set(&mut deps, weight_curve).unwrap_err();
+            assert_eq!(err, ContractError::InvalidWeightCurve);
+        }
+        set(&mut deps, WeightCurve::Sqrt).unwrap();
+
+        let weight = |weight_curve, precision, score| {
+            let round = Round {
+                precision,
+                weight_curve,
+                ..Round::default()
+            };
+            QGContract::curve_weight(&round, score).unwrap()
+        };
+        assert_eq!(weight(WeightCurve::Log2, 1, 0), 10);
+        assert_eq!(weight(WeightCurve::Log2, 1, 123143400), 268);
+        assert_eq!(weight(WeightCurve::Log2, 4, 1), 15849);
+        assert_eq!(weight(WeightCurve::Log2, 4, u64::MAX), 639999);
+        assert_eq!(weight(WeightCurve::Sqrt, 1, 0), 10);
+        assert_eq!(weight(WeightCurve::Sqrt, 3, 99), 10000);
+        let linear = || WeightCurve::Linear {
+            slope: Decimal::permille(5),
+            cap: Decimal::percent(300),
+        };
+        assert_eq!(weight(linear(), 2, 100), 150);
+        assert_eq!(weight(linear(), 2, 1000), 300);
+        let piecewise = || WeightCurve::Piecewise {
+            steps: vec![
+                WeightStep {
+                    min_score: 100,
+                    weight: Decimal::percent(150),
+                },
+                WeightStep {
+                    min_score: 1000,
+                    weight: Decimal::percent(250),
+                },
+            ],
+        };
+        assert_eq!(weight(piecewise(), 1, 99), 10);
+        assert_eq!(weight(piecewise(), 1, 100), 15);
+        assert_eq!(weight(piecewise(), 1, 5000), 25);
+        let constant = WeightCurve::Constant {
+            weight: Decimal::percent(120),
+        };
+        assert_eq!(weight(constant, 2, 5000), 120);
+
+        // The curve cannot change once the round has votes
+        vote(&mut deps, &env, "user1", vec![1], vec![10000]).unwrap();
+        let err = set(&mut deps, WeightCurve::Log2).unwrap_err();
+        assert_eq!(err, ContractError::VotingStarted { round_id: 1 });
+    }
+
+    #[test]
+    fn attested_score_type() {
+        let (mut deps, env) = setup_round(1);
+
+        let set = |score_type: &str| ExecMsg::SetScoreType {
+            round_id: 1,
+            score_type: score_type.to_string(),
+        };
+        exec(&mut deps, &env, "user1", set("passport")).unwrap_err();
+        let err = exec(&mut deps, &env, "admin1", set(&"x".repeat(65))).unwrap_err();
+        assert_eq!(err, ContractError::ScoreTypeTooLong { max: 64 });
+
+        let msg = ExecMsg::GrantRole {
+            address: "signer".to_string(),
+            role: Role::SignerManager,
+        };
+        exec(&mut deps, &env, "owner", msg).unwrap();
+        let resp = exec(&mut deps, &env, "signer", set("passport")).unwrap();
+        assert_eq!(resp.events[0].attributes[1].value, "passport");
+
+        let msg = QueryMsg::Round { round_id: 1 };
+        let resp = query(
+            deps.as_ref(),
+            env.clone(),
+            ContractQueryMsg::QGContract(msg),
+        )
+        .unwrap();
+        let round: Round = from_json(&resp).unwrap();
+        assert_eq!(round.score_type, "passport");
+
+        // Unsigned ballots still count with weight 1.0
+        vote(&mut deps, &env, "user1", vec![1], vec![10000]).unwrap();
+        let err = exec(&mut deps, &env, "signer", set("kyc")).unwrap_err();
+        assert_eq!(err, ContractError::VotingStarted { round_id: 1 });
+    }
+
+    #[test]
+    fn staking_weight_source() {
+        let (mut deps, env) = setup_round(3);
+        deps.querier.update_staking(
+            "inj",
+            &[],
+            &[FullDelegation {
+                delegator: Addr::unchecked("user1"),
+                validator: "validator".to_string(),
+                amount: Coin::new(6000, "inj"),
+                can_redelegate: Coin::new(0, "inj"),
+                accumulated_rewards: vec![],
+            }],
+        );
+
+        let set = |snapshot| ExecMsg::SetWeightSource {
+            round_id: 1,
+            weight_source: WeightSource::Staking {
+                unit: Uint128::from(1000u128),
+                snapshot,
+            },
+        };
+        exec(&mut deps, &env, "user1", set(false)).unwrap_err();
+        let msg = ExecMsg::SetWeightSource {
+            round_id: 1,
+            weight_source: WeightSource::Staking {
+                unit: Uint128::zero(),
+                snapshot: false,
+            },
+        };
+        let err = exec(&mut deps, &env, "admin1", msg).unwrap_err();
+        assert_eq!(err, ContractError::StakingUnitZero);
+        exec(&mut deps, &env, "admin1", set(false)).unwrap();
+
+        let area = |deps: &MockDeps, project_id| -> u128 {
+            let msg = QueryMsg::Project {
+                round_id: 1,
+                project_id,
+            };
+            let resp = query(deps.as_ref(), mock_env(), ContractQueryMsg::QGContract(msg)).unwrap();
+            from_json::<Project>(&resp).unwrap().area
+        };
+        // 6 staked units weigh log2(6 + 2) = 3.0, no stake weighs log2(2) = 1.0
+        vote(&mut deps, &env, "user1", vec![1], vec![10000]).unwrap();
+        vote(&mut deps, &env, "user2", vec![2], vec![10000]).unwrap();
+        assert_eq!(area(&deps, 1), 3000);
+        assert_eq!(area(&deps, 2), 1000);
+        let err = exec(&mut deps, &env, "admin1", set(true)).unwrap_err();
+        assert_eq!(err, ContractError::VotingStarted { round_id: 1 });
+
+        // A snapshot round ignores live delegations
+        let (mut deps, env) = setup_round(1);
+        exec(&mut deps, &env, "admin1", set(true)).unwrap();
+        let msg = ExecMsg::UploadStakeSnapshot {
+            round_id: 1,
+            voters: vec!["user2".to_string()],
+            amounts: vec![Uint128::from(14000u128)],
+        };
+        exec(&mut deps, &env, "admin1", msg).unwrap();
+        vote(&mut deps, &env, "user2", vec![1], vec![10000]).unwrap();
+        assert_eq!(area(&deps, 1), 4000);
+    }
+
+    #[test]
+    fn weight_policies() {
+        let stake = |deps: &mut MockDeps, amount| {
+            deps.querier.update_staking(
+                "inj",
+                &[],
+                &[FullDelegation {
+                    delegator: Addr::unchecked("user1"),
+                    validator: "validator".to_string(),
+                    amount: Coin::new(amount, "inj"),
+                    can_redelegate: Coin::new(0, "inj"),
+                    accumulated_rewards: vec![],
+                }],
+            );
+        };
+        let setup = |weight_policy| {
+            let (mut deps, env) = setup_round(2);
+            let msg = ExecMsg::SetWeightSource {
+                round_id: 1,
+                weight_source: WeightSource::Staking {
+                    unit: Uint128::from(1000u128),
+                    snapshot: false,
+                },
+            };
+            exec(&mut deps, &env, "admin1", msg).unwrap();
+            let msg = ExecMsg::SetWeightPolicy {
+                round_id: 1,
+                weight_policy,
+            };
+            exec(&mut deps, &env, "user1", msg.clone()).unwrap_err();
+            exec(&mut deps, &env, "admin1", msg).unwrap();
+            // 6 staked units weigh log2(6 + 2) = 3.0
+            stake(&mut deps, 6000);
+            vote(&mut deps, &env, "user1", vec![1], vec![10000]).unwrap();
+            // 2 staked units weigh log2(2 + 2) = 2.0
+            stake(&mut deps, 2000);
+            (deps, env)
+        };
+        let area = |deps: &MockDeps, project_id| -> u128 {
+            let msg = QueryMsg::Project {
+                round_id: 1,
+                project_id,
+            };
+            let resp = query(deps.as_ref(), mock_env(), ContractQueryMsg::QGContract(msg)).unwrap();
+            from_json::<Project>(&resp).unwrap().area
+        };
+        let weight = |deps: &MockDeps| {
+            let msg = QueryMsg::VoterWeight {
+                round_id: 1,
+                voter: "user1".to_string(),
+            };
+            let resp = query(deps.as_ref(), mock_env(), ContractQueryMsg::QGContract(msg)).unwrap();
+            from_json::<VoterWeightResp>(&resp).unwrap()
+        };
+
+        let (mut deps, env) = setup(WeightPolicy::Latest);
+        vote(&mut deps, &env, "user1", vec![2], vec![10000]).unwrap();
+        assert_eq!(area(&deps, 2), 2000);
+        assert_eq!(weight(&deps).weight, Some(20));
+
+        let (mut deps, env) = setup(WeightPolicy::Pinned);
+        vote(&mut deps, &env, "user1", vec![2], vec![10000]).unwrap();
+        assert_eq!(area(&deps, 2), 3000);
+        assert_eq!(
+            weight(&deps),
+            VoterWeightResp {
+                round_id: 1,
+                voter: "user1".to_string(),
+                policy: WeightPolicy::Pinned,
+                weight: Some(30),
+            }
+        );
+        // Pinned weights follow the round to a higher precision
+        migrate(
+            deps.as_mut(),
+            env.clone(),
+            MigrateMsg { precision: Some(4) },
+        )
+        .unwrap();
+        assert_eq!(weight(&deps).weight, Some(30000));
+        vote(&mut deps, &env, "user1", vec![2], vec![10000]).unwrap();
+        assert_eq!(area(&deps, 2), 3000000 + 1414213 * 3 - 1000000);
+
+        let (mut deps, env) = setup(WeightPolicy::Monotonic);
+        let err = vote(&mut deps, &env, "user1", vec![2], vec![10000]).unwrap_err();
+        assert_eq!(
+            err,
+            ContractError::WeightDecreased {
+                pinned: 30,
+                weight: 20
+            }
+        );
+        // 14 staked units weigh log2(14 + 2) = 4.0
+        stake(&mut deps, 14000);
+        vote(&mut deps, &env, "user1", vec![2], vec![10000]).unwrap();
+        assert_eq!(area(&deps, 2), 4000);
+        assert_eq!(weight(&deps).weight, Some(40));
+
+        // Unsigned ballots count as 1.0 without pinning it
+        let (mut deps, env) = setup_round(2);
+        vote(&mut deps, &env, "user1", vec![1], vec![10000]).unwrap();
+        assert_eq!(area(&deps, 1), 1000);
+        assert_eq!(weight(&deps).policy, WeightPolicy::Pinned);
+        assert_eq!(weight(&deps).weight, None);
+    }
+
+    #[test]
+    fn retract_votes() {
+        let (mut deps, env) = setup_round(3);
+        let retract = |deps: &mut MockDeps, voter, project_ids, amounts: Vec<u128>| {
+            let msg = ExecMsg::RetractVotes {
+                round_id: 1,
+                project_ids,
+                amounts: amounts.into_iter().map(Uint128::from).collect(),
+            };
+            exec(deps, &env, voter, msg)
+        };
+        let project_of = |deps: &MockDeps, project_id| -> Project {
+            let msg = QueryMsg::Project {
+                round_id: 1,
+                project_id,
+            };
+            let resp = query(deps.as_ref(), mock_env(), ContractQueryMsg::QGContract(msg)).unwrap();
+            from_json(&resp).unwrap()
+        };
+        let round_of = |deps: &MockDeps| -> Round {
+            let msg = QueryMsg::Round { round_id: 1 };
+            let resp = query(deps.as_ref(), mock_env(), ContractQueryMsg::QGContract(msg)).unwrap();
+            from_json(&resp).unwrap()
+        };
+
+        vote(&mut deps, &env, "user1", vec![1, 2], vec![10000, 10000]).unwrap();
+        vote(&mut deps, &env, "user2", vec![1], vec![10000]).unwrap();
+        assert_eq!(project_of(&deps, 1).area, 2000);
+
+        let err = retract(&mut deps, "user1", vec![1], vec![10001]).unwrap_err();
+        assert_eq!(
+            err,
+            ContractError::RetractExceedsContribution {
+                project_id: 1,
+                contribution: 10000
+            }
+        );
+        retract(&mut deps, "user3", vec![1], vec![1]).unwrap_err();
+
+        // Retracting everything leaves the project as if the voter never funded it
+        let resp = retract(&mut deps, "user1", vec![1], vec![10000]).unwrap();
+        assert_eq!(
+            resp.messages[0].msg,
+            BankMsg::Send {
+                to_address: "user1".to_string(),
+                amount: coins(10000, "inj"),
+            }
+            .into()
+        );
+        let project = project_of(&deps, 1);
+        assert_eq!(
+            (project.area, project.votes, project.contribution),
+            (1000, 10000, 10000)
+        );
+        assert_eq!(project.voter_count, 1);
+
+        // A partial retraction takes votes back pro rata, sqrt(2500) is half of sqrt(10000)
+        retract(&mut deps, "user1", vec![2], vec![7500]).unwrap();
+        let project = project_of(&deps, 2);
+        assert_eq!(
+            (project.area, project.votes, project.contribution),
+            (500, 2500, 2500)
+        );
+        let round = round_of(&deps);
+        assert_eq!((round.total_area, round.voter_count), (1500, 2));
+
+        retract(&mut deps, "user1", vec![2], vec![2500]).unwrap();
+        let round = round_of(&deps);
+        assert_eq!((round.total_area, round.total_amounts), (1000, 10000));
+        assert_eq!(round.voter_count, 1);
+
+        // Moving support is retracting and voting again
+        vote(&mut deps, &env, "user1", vec![3], vec![10000]).unwrap();
+        assert_eq!(project_of(&deps, 3).area, 1000);
+        assert_eq!(round_of(&deps).voter_count, 2);
+
+        // Pairwise areas end up where they would be had the vote never been cast
+        let strategy = MatchingStrategy::Pairwise { threshold: 10000 };
+        let areas = |deps: &MockDeps| -> Vec<u128> {
+            let msg = QueryMsg::PairwiseAreas {
+                round_id: 1,
+                start_after: None,
+                limit: None,
+            };
+            let resp = query(deps.as_ref(), mock_env(), ContractQueryMsg::QGContract(msg)).unwrap();
+            let resp: PairwiseAreasResp = from_json(&resp).unwrap();
+            resp.areas.iter().map(|area| area.area).collect()
+        };
+        let (mut deps, env) = setup_round_with(2, Some(strategy));
+        vote(&mut deps, &env, "user1", vec![1, 2], vec![10000, 10000]).unwrap();
+        vote(&mut deps, &env, "user2", vec![1, 2], vec![10000, 10000]).unwrap();
+        assert_eq!(areas(&deps), vec![333333, 333333]);
+        retract(&mut deps, "user1", vec![1], vec![10000]).unwrap();
+        assert_eq!(areas(&deps), vec![0, 500000]);
+    }
+
+    #[test]
+    fn retract_votes_after_fee_change() {
+        let (mut deps, env) = setup_round(2);
+        let set_fee = |deps: &mut MockDeps, fee_bps| {
+            let msg = ExecMsg::SetFee {
+                round_id: 1,
+                fee_bps,
+            };
+            exec(deps, &env, "admin1", msg).unwrap();
+        };
+        let retract = |deps: &mut MockDeps, project_id, amount: u128| {
+            let msg = ExecMsg::RetractVotes {
+                round_id: 1,
+                project_ids: vec![project_id],
+                amounts: vec![Uint128::from(amount)],
+            };
+            exec(deps, &env, "user1", msg)
+        };
+        let refund = |resp: Response| match &resp.messages[0].msg {
+            CosmosMsg::Bank(BankMsg::Send { amount, .. }) => amount[0].amount.u128(),
+            msg => panic!("unexpected message {:?}", msg),
+        };
+        let round_of = |deps: &MockDeps| -> Round {
+            let msg = QueryMsg::Round { round_id: 1 };
+            let resp = query(deps.as_ref(), mock_env(), ContractQueryMsg::QGContract(msg)).unwrap();
+            from_json(&resp).unwrap()
+        };
+
+        // 10% of each donation is kept as fee when it is cast
+        set_fee(&mut deps, 1000);
+        vote(&mut deps, &env, "user1", vec![1, 2], vec![10000, 10000]).unwrap();
+        vote(&mut deps, &env, "user2", vec![1], vec![10000]).unwrap();
+        set_fee(&mut deps, 0);
+        vote(&mut deps, &env, "user1", vec![1], vec![10000]).unwrap();
+
+        // Refunds follow the fees paid back then, not the current one
+        assert_eq!(refund(retract(&mut deps, 1, 5000).unwrap()), 4750);
+        assert_eq!(refund(retract(&mut deps, 1, 15000).unwrap()), 14250);
+        set_fee(&mut deps, 5000);
+        assert_eq!(refund(retract(&mut deps, 2, 3333).unwrap()), 2999);
+        assert_eq!(refund(retract(&mut deps, 2, 6667).unwrap()), 6001);
+
+        let round = round_of(&deps);
+        assert_eq!((round.total_amounts, round.fees), (9000, 3000));
+        assert_eq!(round.total_area, 1000);
+        let msg = QueryMsg::Project {
+            round_id: 1,
+            project_id: 2,
+        };
+        let resp = query(deps.as_ref(), mock_env(), ContractQueryMsg::QGContract(msg)).unwrap();
+        let project: Project = from_json(&resp).unwrap();
+        assert_eq!(
+            (project.area, project.votes, project.contribution),
+            (0, 0, 0)
+        );
+
+        // Donations without a recorded net amount can't be refunded exactly
+        vote(&mut deps, &env, "user1", vec![2], vec![10000]).unwrap();
+        QGContract::new()
+            .net_contributions
+            .remove(deps.as_mut().storage, ("1", "2", &Addr::unchecked("user1")));
+        let err = retract(&mut deps, 2, 10000).unwrap_err();
+        assert_eq!(err, ContractError::RetractNotTracked { project_id: 2 });
+    }
 }
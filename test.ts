@@ -78,7 +78,7 @@ async function main() {
   //       round_id: 1,
   //       project_ids: [1],
   //       amounts: ["160000"],
-  //       vcdora: 0,
+  //       score: 0,
   //       timestamp: 0,
   //       recid: 0,
   //       sig: [],
@@ -95,7 +95,7 @@ async function main() {
   //       round_id: 1,
   //       project_ids: [1],
   //       amounts: ["90000"],
-  //       vcdora: 0,
+  //       score: 0,
   //       timestamp: 0,
   //       recid: 0,
   //       sig: [],
@@ -110,7 +110,7 @@ async function main() {
   //   weighted_batch_vote: {
   //     round_id: 1,
   //     project_votes: [[2, "160000"]],
-  //     vcdora: 123143400,
+  //     score: 123143400,
   //   },
   // });
   // console.log("weighted_batch_vote3: ", txExecHash.txHash);